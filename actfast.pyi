@@ -1,12 +1,72 @@
 """Fast actigraphy data reader for Python, written in Rust."""
 
+from collections.abc import Iterator
+from datetime import datetime
 from os import PathLike
-from typing import Required, TypedDict
+from typing import Literal, Protocol, Required, TypedDict
 
 import numpy as np
 from numpy.typing import NDArray
 
 
+class ActfastError(ValueError):
+    """Base class for errors in the contents of an actigraphy file.
+
+    The location attributes are set when the error can be traced to a
+    position in the file, and are None otherwise.
+    """
+
+    byte_offset: int | None
+    """Byte offset in the file."""
+    record_index: int | None
+    """Index of the record, page or sector (0-based)."""
+    sample_index: int | None
+    """Index of the sample within the record (0-based)."""
+    line_number: int | None
+    """Line number for text-based formats (1-based)."""
+
+
+class UnknownFormatError(ActfastError):
+    """The file format could not be identified."""
+
+
+class UnsupportedFormatError(ActfastError):
+    """The file format is recognized but cannot be read."""
+
+
+class ParseError(ActfastError):
+    """The file could not be parsed."""
+
+
+class InvalidFieldError(ActfastError):
+    """A field holds an invalid value."""
+
+
+class UnexpectedEofError(ActfastError):
+    """The file ended unexpectedly."""
+
+
+class InvalidDateTimeError(ActfastError):
+    """A date/time could not be parsed."""
+
+
+class InvalidHexError(ActfastError):
+    """Hex-encoded data could not be decoded."""
+
+
+class BitReadError(ActfastError):
+    """Packed binary sample data could not be read."""
+
+
+MetadataValue = int | float | bool | datetime | str
+"""A metadata value.
+
+Numbers, flags and timestamps are parsed by each reader, anything else is
+kept as the string from the file. Datetimes are timezone-aware (UTC); local
+times stored in the file are taken as UTC, like the sample timestamps.
+"""
+
+
 class TimeseriesData(TypedDict, total=False):
     """Timeseries data from a sensor.
     
@@ -17,40 +77,260 @@ class TimeseriesData(TypedDict, total=False):
     datetime: Required[NDArray[np.int64]]
     acceleration: NDArray[np.float32]
     gyroscope: NDArray[np.float32]
+    magnetometer: NDArray[np.float32]
+    magnetometer_counts: NDArray[np.float32]
+    """Uncalibrated Axivity magnetometer readings in sensor counts."""
     light: NDArray[np.float32] | NDArray[np.uint16]
     temperature: NDArray[np.float32]
     battery_voltage: NDArray[np.float32] | NDArray[np.uint16]
     button_state: NDArray[np.bool_]
     capsense: NDArray[np.bool_]
+    counts: NDArray[np.uint32]
+    steps: NDArray[np.uint32]
+    """Steps per epoch, the uint32 maximum for epochs without steps."""
+    inclinometer: NDArray[np.uint8]
+    """Inclinometer state per epoch, 255 for epochs without one."""
+    heart_rate: NDArray[np.uint16]
+    rr_interval: NDArray[np.float32]
+
+
+IdleSleep = Literal["leave", "fill_last", "nan"]
+"""How to treat ActiGraph idle sleep gaps, see `read`."""
+
+Timestamps = Literal["block", "interpolated"]
+"""How to time the samples of Axivity data sectors, see `read`."""
+
+
+class Segment(TypedDict):
+    """A run of Axivity data sectors with the same configuration, see `read`."""
+
+    start_sector: int
+    start_time: int
+    sample_rate: float
+    accelerometer_range: int
+    gyroscope_range: float | None
+    axes: int
 
 
 class ActfastResult(TypedDict):
     """Result from reading an actigraphy file."""
 
     format: str
-    metadata: dict[str, dict[str, str]]
+    metadata: dict[str, dict[str, MetadataValue]]
     timeseries: dict[str, TimeseriesData]
     warnings: list[str]
+    filled_spans: list[tuple[int, int]]
+    events: list[tuple[int, str, bytes]]
+    checksum_mismatches: int
+    effective_sample_rate: float | None
+    segments: list[Segment]
+
+
+class InfoResult(TypedDict):
+    """Result from inspecting an actigraphy file, see `info`."""
+
+    format: str
+    metadata: dict[str, dict[str, MetadataValue]]
+    first_timestamp: int | None
+    last_timestamp: int | None
+    sample_rate: float | None
+    estimated_samples: int | None
+    sensors: list[str]
+
+
+class Chunk(TypedDict):
+    """One chunk of a recording, see `File.iter_chunks`."""
+
+    timeseries: dict[str, TimeseriesData]
+
+
+class ChunkIterator(Iterator[Chunk]):
+    """Iterator over the chunks of a file.
+
+    Decoding runs on a background thread that stays at most one chunk ahead
+    of the consumer, so memory use is bounded by the chunk size.
+    """
+
+    @property
+    def format(self) -> str:
+        """File format name."""
+        ...
 
+    @property
+    def metadata(self) -> dict[str, dict[str, MetadataValue]]:
+        """Metadata encountered so far (usually complete after the first chunk)."""
+        ...
 
-def read(path: str | PathLike[str], lenient: bool = False) -> ActfastResult:
+    @property
+    def warnings(self) -> list[str]:
+        """Warnings, complete once the iterator is exhausted."""
+        ...
+
+    @property
+    def filled_spans(self) -> list[tuple[int, int]]:
+        """Filled idle sleep spans, complete once the iterator is exhausted."""
+        ...
+
+    @property
+    def events(self) -> list[tuple[int, str, bytes]]:
+        """Device events, complete once the iterator is exhausted."""
+        ...
+
+    @property
+    def checksum_mismatches(self) -> int:
+        """Records that failed their checksum, complete once the iterator is exhausted."""
+        ...
+
+    @property
+    def effective_sample_rate(self) -> float | None:
+        """Observed sample rate (Axivity only), set once the iterator is exhausted."""
+        ...
+
+    @property
+    def segments(self) -> list[Segment]:
+        """Configuration segments (Axivity only), complete once the iterator is exhausted."""
+        ...
+
+    def __iter__(self) -> "ChunkIterator": ...
+    def __next__(self) -> Chunk: ...
+
+
+class File:
+    """An actigraphy file opened for chunked reading, see `open`."""
+
+    @property
+    def format(self) -> str:
+        """File format name."""
+        ...
+
+    def iter_chunks(
+        self,
+        samples: int = 1_000_000,
+        lenient: bool = False,
+        start: datetime | int | None = None,
+        end: datetime | int | None = None,
+        sensors: list[str] | None = None,
+        idle_sleep: IdleSleep = "leave",
+        timestamps: Timestamps = "block",
+    ) -> ChunkIterator:
+        """Iterate over the recording in time-ordered chunks.
+
+        Each chunk contains the sensor tables found so far, holding only the
+        samples decoded since the previous chunk. Tables and columns that
+        depend on the records in the file (such as the ActiGraph `imu`,
+        `epoch_counts` and `heart_rate` tables) first appear in the chunk
+        that decodes their first record, so earlier chunks lack them.
+        Otherwise concatenating all chunks gives the same result as `read`.
+
+        Args:
+            samples: Approximate number of samples of the highest rate sensor
+                table per chunk. Chunks end on record boundaries, so they can be
+                slightly larger.
+            lenient: If True, return partial data on corruption instead of raising.
+            start: Only return samples at or after this time, see `read`.
+            end: Only return samples before this time, see `read`.
+            sensors: Only decode these sensors, see `read`.
+            idle_sleep: Reconstruction of idle sleep gaps, see `read`.
+            timestamps: Timing of Axivity samples, see `read`.
+
+        Example:
+            >>> chunks = actfast.open("subject1.cwa").iter_chunks(samples=360_000)
+            >>> for chunk in chunks:
+            ...     process(chunk["timeseries"]["high_frequency"])
+            >>> chunks.warnings
+        """
+        ...
+
+
+def open(path: str | PathLike[str]) -> File:
+    """Open a raw actigraphy file for chunked reading.
+
+    The file format is identified immediately, data is only decoded once
+    `File.iter_chunks` is iterated.
+
+    Raises:
+        UnknownFormatError: If the file format is unknown.
+        UnsupportedFormatError: If the file format is not supported.
+        OSError: If the file cannot be read.
+    """
+    ...
+
+
+class BinaryReader(Protocol):
+    """A binary file-like object, such as `io.BytesIO` or `open(..., "rb")`."""
+
+    def read(self, size: int = ..., /) -> bytes: ...
+    def seek(self, offset: int, whence: int = ..., /) -> int: ...
+
+
+def read(
+    path: str | PathLike[str] | bytes | bytearray | BinaryReader,
+    lenient: bool = False,
+    start: datetime | int | None = None,
+    end: datetime | int | None = None,
+    sensors: list[str] | None = None,
+    idle_sleep: IdleSleep = "leave",
+    timestamps: Timestamps = "block",
+) -> ActfastResult:
     """Read a raw actigraphy file.
 
+    The GIL is released while decoding, so several files can be read in
+    parallel from Python threads (e.g. a `ThreadPoolExecutor`).
+
     Args:
-        path: Path to the actigraphy file (.gt3x, .bin, .cwa).
+        path: Path to the actigraphy file (.gt3x, .bin, .cwa), its contents
+            as bytes, or a binary file-like object with `read` and `seek`.
+            File-like objects are read from their beginning.
         lenient: If True, return partial data on corruption instead of raising.
             Any issues encountered will be reported in the `warnings` field.
+            Corrupt ActiGraph log records are skipped, reading resumes at
+            the next valid record.
+        start: Only return samples at or after this time. Either a datetime
+            (naive datetimes are taken as UTC, like the returned timestamps)
+            or int64 nanoseconds since the Unix epoch.
+        end: Only return samples before this time, same types as `start`.
+            Data outside the window is skipped without being decoded.
+        sensors: Only decode these sensors, by column name ("acceleration",
+            "gyroscope", "magnetometer", "magnetometer_counts", "light",
+            "button_state", "capsense", "temperature", "battery_voltage",
+            "counts", "steps", "inclinometer", "heart_rate", "rr_interval").
+            Tables left without columns are omitted.
+            Defaults to all sensors.
+        idle_sleep: How to treat the seconds an ActiGraph device spent in
+            idle sleep mode, during which it logs no samples. "leave" keeps
+            the gaps, "fill_last" repeats the last sample before each gap
+            (like ActiLife) and "nan" fills them with NaN. Ignored for other
+            formats and legacy GT3X files (`activity.bin`).
+        timestamps: How to time the samples of Axivity data sectors. "block"
+            spaces them at the nominal rate from each sector's own timestamp,
+            "interpolated" spaces them evenly between consecutive sector
+            timestamps (like the OpenMovement tools and GGIRread), which
+            removes jitter at sector boundaries. Ignored for other formats.
 
     Returns:
         Dictionary containing:
         - `format`: File format name (e.g., "Actigraph GT3X", "GeneActiv BIN", "Axivity CWA")
         - `metadata`: Device-specific metadata as nested dicts
         - `timeseries`: Sensor data with `datetime` (int64 nanoseconds) and sensor arrays
-        - `warnings`: List of warnings (only populated when `lenient=True`)
+        - `warnings`: List of warnings, such as parse errors (only when
+          `lenient=True`) or data loss reported by the device
+        - `filled_spans`: `(start, end)` nanosecond spans filled with
+          reconstructed samples (only populated if `idle_sleep` fills)
+        - `events`: `(time, kind, payload)` tuples of device events and
+          diagnostic records (ActiGraph and Axivity), see the README
+        - `checksum_mismatches`: Number of records (or sectors) that failed
+          their checksum, which are skipped when `lenient=True`
+        - `effective_sample_rate`: Sample rate in Hz observed between the
+          sector timestamps (Axivity only, otherwise None)
+        - `segments`: Configuration of each run of data sectors read, with
+          its first sector and sample time, sample rate, ranges and number
+          of axes (Axivity only, otherwise empty)
 
     Raises:
-        ValueError: If the file format is unknown, unsupported, or malformed
-            (when `lenient=False`).
+        ActfastError: If the file format is unknown, unsupported, or malformed
+            (when `lenient=False`). The subclass tells which, see `ActfastError`.
+        ValueError: If a sensor name, idle sleep mode or timestamp mode is unknown.
+        TypeError: If `path` is not a path, bytes or a file-like object.
         OSError: If the file cannot be read.
 
     Example:
@@ -58,9 +338,50 @@ def read(path: str | PathLike[str], lenient: bool = False) -> ActfastResult:
         >>> data["timeseries"]["acceleration"]["datetime"]  # int64 timestamps
         >>> data["timeseries"]["acceleration"]["acceleration"]  # float32 (n, 3)
 
+        >>> # Only decode one day:
+        >>> data = actfast.read("subject1.cwa", start=datetime(2024, 5, 1), end=datetime(2024, 5, 2))
+
+        >>> # Read from memory without writing to disk:
+        >>> data = actfast.read(blob)  # bytes
+        >>> data = actfast.read(io.BytesIO(blob))
+
+        >>> # Skip decoding everything but the accelerometer:
+        >>> data = actfast.read("subject1.bin", sensors=["acceleration"])
+
         >>> # For corrupted files, use lenient mode:
         >>> data = actfast.read("corrupted.gt3x", lenient=True)
         >>> if data["warnings"]:
         ...     print(f"Recovered partial data with {len(data['warnings'])} warnings")
     """
-    ...
\ No newline at end of file
+    ...
+
+
+def info(path: str | PathLike[str] | bytes | bytearray | BinaryReader) -> InfoResult:
+    """Read the metadata and a summary of a raw actigraphy file.
+
+    Only the file header and the first/last records are read, no sample data
+    is decoded, so this is much faster than `read` on large files.
+
+    Args:
+        path: Same as for `read`.
+
+    Returns:
+        Dictionary containing:
+        - `format`: File format name, see `read`
+        - `metadata`: Same metadata as returned by `read`
+        - `first_timestamp`, `last_timestamp`: Time of the first and last
+          sample (int64 nanoseconds since the Unix epoch), if known
+        - `sample_rate`: Nominal sample rate in Hz of the highest rate sensor
+        - `estimated_samples`: Estimated number of samples of that sensor
+        - `sensors`: Names of the sensors in the file (as accepted by `read`)
+
+    Raises:
+        ActfastError: If the file format is unknown, unsupported, or malformed.
+        TypeError: If `path` is not a path, bytes or a file-like object.
+        OSError: If the file cannot be read.
+
+    Example:
+        >>> summary = actfast.info("subject1.cwa")
+        >>> summary["last_timestamp"] - summary["first_timestamp"]  # duration in ns
+    """
+    ...
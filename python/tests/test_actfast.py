@@ -1,5 +1,6 @@
 import numpy as np
 import pytest
+from datetime import datetime, timezone
 from pathlib import Path
 from tempfile import NamedTemporaryFile
 
@@ -47,6 +48,40 @@ class TestRead:
         assert "info" in metadata
         assert "Sample Rate" in metadata["info"]
 
+    def test_geneactiv_typed_metadata(self, geneactiv_file):
+        """Test that GeneActiv header values are parsed to Python types."""
+        metadata = actfast.read(geneactiv_file)["metadata"]
+
+        assert metadata["Configuration Info"]["Measurement Frequency"] == 60.0
+        assert metadata["Calibration Data"]["x gain"] == 25270
+        assert metadata["Configuration Info"]["Start Time"] == datetime(
+            2024, 4, 30, 15, 13, 30, tzinfo=timezone.utc
+        )
+        assert metadata["Device Identity"]["Device Type"] == "GENEActiv"
+
+    def test_actigraph_typed_metadata(self, actigraph_file):
+        """Test that GT3X info.txt ticks are converted to datetimes."""
+        metadata = actfast.read(actigraph_file)["metadata"]
+
+        assert metadata["info"]["Start Date"] == datetime(
+            2024, 4, 30, 14, 53, tzinfo=timezone.utc
+        )
+        assert metadata["info"]["Acceleration Scale"] == 256.0
+        assert metadata["device_feature_enabled"]["no_raw_data"] is False
+
+    def test_actigraph_parameters(self, actigraph_file):
+        """Test that GT3X Parameters records are typed."""
+        parameters = actfast.read(actigraph_file)["metadata"]["parameters"]
+
+        assert parameters["FirmwareVersion"] == "1.9.2"
+        assert parameters["BoardRevision"] == 5
+        assert parameters["AccelScale"] == 256.0
+        assert parameters["NegativeGOffsetX"] == -166
+        assert parameters["CalibrationTime"] == datetime(
+            2021, 4, 27, 14, 20, 17, tzinfo=timezone.utc
+        )
+        assert parameters["0:37"] == 1024
+
     def test_geneactiv_timeseries(self, geneactiv_file):
         """Test GeneActiv timeseries data."""
         result = actfast.read(geneactiv_file)
@@ -95,6 +130,19 @@ class TestRead:
         assert acc.shape[0] == len(dt)
         assert acc.shape[1] == 3
 
+    def test_arrays_are_not_copied(self, geneactiv_file):
+        """Test that decoded buffers are handed to numpy without a copy."""
+        hf = actfast.read(geneactiv_file)["timeseries"]["high_frequency"]
+        acc = hf["acceleration"]
+
+        # The (n, 3) array is a view of the 1-D buffer decoded in Rust
+        assert acc.flags["C_CONTIGUOUS"]
+        assert acc.flags["WRITEABLE"]
+        assert acc.base is not None
+        assert acc.base.shape == (acc.size,)
+        assert not acc.base.flags["OWNDATA"]
+        assert not hf["datetime"].flags["OWNDATA"]
+
     def test_actigraph_acceleration_shape(self, actigraph_file):
         """Test that acceleration data has correct shape."""
         result = actfast.read(actigraph_file)
@@ -136,6 +184,211 @@ class TestRead:
         np.testing.assert_almost_equal(acc_table["acceleration"][0, 2], -0.636719, decimal=5)
 
 
+class TestInfo:
+    """Tests for actfast.info()."""
+
+    def test_geneactiv_info(self, geneactiv_file):
+        """Test that the summary matches a full read."""
+        data = actfast.read(geneactiv_file)
+        summary = actfast.info(geneactiv_file)
+        dt = data["timeseries"]["high_frequency"]["datetime"]
+
+        assert summary["format"] == data["format"]
+        assert summary["metadata"] == data["metadata"]
+        assert summary["first_timestamp"] == dt[0]
+        assert summary["last_timestamp"] == dt[-1]
+        assert summary["sample_rate"] == 60.0
+        assert summary["estimated_samples"] == len(dt)
+        assert "acceleration" in summary["sensors"]
+
+    def test_actigraph_info(self, actigraph_file):
+        """Test the GT3X summary from info.txt and the parameters record."""
+        summary = actfast.info(actigraph_file)
+
+        assert summary["format"] == "Actigraph GT3X"
+        assert summary["metadata"]["info"]["Sample Rate"] == 60
+        assert summary["sample_rate"] == 60.0
+        assert summary["first_timestamp"] < summary["last_timestamp"]
+        assert summary["sensors"][0] == "acceleration"
+
+    def test_info_unknown_format(self, tmp_path):
+        """Test that info() rejects unknown formats like read()."""
+        test_file = tmp_path / "unknown.xyz"
+        test_file.write_bytes(b"\x00\x01\x02\x03" + b"\x00" * 100)
+
+        with pytest.raises(ValueError):
+            actfast.info(test_file)
+
+
+class TestTimeWindow:
+    """Tests for the start/end arguments of actfast.read()."""
+
+    def test_window_int_nanoseconds(self, geneactiv_file):
+        """Test that only samples in [start, end) are returned."""
+        whole = actfast.read(geneactiv_file)["timeseries"]["high_frequency"]["datetime"]
+        start, end = whole[1000], whole[2000]
+
+        result = actfast.read(geneactiv_file, start=int(start), end=int(end))
+        dt = result["timeseries"]["high_frequency"]["datetime"]
+
+        np.testing.assert_array_equal(dt, whole[(whole >= start) & (whole < end)])
+
+    def test_window_datetime(self, actigraph_file):
+        """Test that naive and aware datetimes are both interpreted correctly."""
+        naive = actfast.read(
+            actigraph_file,
+            start=datetime(2024, 4, 30, 14, 53, 10),
+            end=datetime(2024, 4, 30, 14, 53, 20),
+        )
+        aware = actfast.read(
+            actigraph_file,
+            start=datetime(2024, 4, 30, 14, 53, 10, tzinfo=timezone.utc),
+            end=datetime(2024, 4, 30, 14, 53, 20, tzinfo=timezone.utc),
+        )
+
+        dt = naive["timeseries"]["acceleration"]["datetime"]
+        assert len(dt) == 600
+        np.testing.assert_array_equal(dt, aware["timeseries"]["acceleration"]["datetime"])
+
+
+class TestSensorSelection:
+    """Tests for the sensors argument of actfast.read()."""
+
+    def test_select_low_frequency(self, geneactiv_file):
+        """Test that unselected columns and empty tables are omitted."""
+        result = actfast.read(geneactiv_file, sensors=["temperature"])
+        timeseries = result["timeseries"]
+
+        assert set(timeseries) == {"low_frequency"}
+        assert set(timeseries["low_frequency"]) == {"datetime", "temperature"}
+
+    def test_selected_data_matches_full_read(self, geneactiv_file):
+        """Test that selected columns are identical to a full read."""
+        whole = actfast.read(geneactiv_file)["timeseries"]["high_frequency"]
+        selected = actfast.read(geneactiv_file, sensors=["acceleration"])["timeseries"]
+
+        np.testing.assert_array_equal(
+            selected["high_frequency"]["acceleration"], whole["acceleration"]
+        )
+        assert "low_frequency" not in selected
+
+    def test_unknown_sensor(self, geneactiv_file):
+        """Test that an unknown sensor name raises ValueError."""
+        with pytest.raises(ValueError, match="unknown sensor"):
+            actfast.read(geneactiv_file, sensors=["accelerometer"])
+
+
+class TestEvents:
+    """Tests for ActiGraph event records."""
+
+    def test_actigraph_events(self, actigraph_file):
+        """Test that event records are returned undecoded."""
+        data = actfast.read(actigraph_file)
+        assert data["events"][1] == (1714488780_000_000_000, "event", b"\x05\x01\x02")
+        for time, kind, payload in data["events"]:
+            assert isinstance(time, int)
+            assert kind in ("event", "tag", "fifo_error", "fifo_dump")
+            assert isinstance(payload, bytes)
+
+    def test_no_events(self, geneactiv_file):
+        """Test that formats without events return an empty list."""
+        assert actfast.read(geneactiv_file)["events"] == []
+
+
+class TestIdleSleep:
+    """Tests for ActiGraph idle sleep gap reconstruction."""
+
+    def test_leave_by_default(self, actigraph_file):
+        """Test that gaps are left in place unless requested."""
+        data = actfast.read(actigraph_file)
+        assert data["filled_spans"] == []
+
+    def test_fill_last(self, actigraph_file):
+        """Test that filled samples repeat the last sample before the gap."""
+        leave = actfast.read(actigraph_file)["timeseries"]["acceleration"]
+        data = actfast.read(actigraph_file, idle_sleep="fill_last")
+        acc = data["timeseries"]["acceleration"]
+
+        assert len(data["filled_spans"]) > 0
+        assert len(acc["datetime"]) > len(leave["datetime"])
+        assert np.all(np.diff(acc["datetime"]) > 0)
+        start, end = data["filled_spans"][0]
+        filled = (acc["datetime"] >= start) & (acc["datetime"] < end)
+        before = np.flatnonzero(filled)[0] - 1
+        expected = np.broadcast_to(acc["acceleration"][before], (filled.sum(), 3))
+        np.testing.assert_array_equal(acc["acceleration"][filled], expected)
+
+    def test_nan(self, actigraph_file):
+        """Test that idle seconds can be filled with NaN."""
+        data = actfast.read(actigraph_file, idle_sleep="nan")
+        acc = data["timeseries"]["acceleration"]
+        start, end = data["filled_spans"][0]
+        filled = (acc["datetime"] >= start) & (acc["datetime"] < end)
+        assert np.isnan(acc["acceleration"][filled]).all()
+
+    def test_unknown_mode(self, actigraph_file):
+        """Test that an unknown mode raises ValueError."""
+        with pytest.raises(ValueError, match="idle_sleep"):
+            actfast.read(actigraph_file, idle_sleep="repeat")
+
+
+class TestTimestamps:
+    """Tests for the Axivity timestamp mode."""
+
+    def test_other_formats(self, geneactiv_file):
+        """Test that the mode is ignored and no rate is reported for other formats."""
+        block = actfast.read(geneactiv_file)
+        data = actfast.read(geneactiv_file, timestamps="interpolated")
+        assert data["effective_sample_rate"] is None
+        assert data["segments"] == []
+        np.testing.assert_array_equal(
+            data["timeseries"]["high_frequency"]["datetime"],
+            block["timeseries"]["high_frequency"]["datetime"],
+        )
+
+    def test_unknown_mode(self, geneactiv_file):
+        """Test that an unknown mode raises ValueError."""
+        with pytest.raises(ValueError, match="timestamps"):
+            actfast.read(geneactiv_file, timestamps="smooth")
+
+
+class TestIterChunks:
+    """Tests for actfast.open() and File.iter_chunks()."""
+
+    def test_open_format(self, geneactiv_file):
+        """Test that open() identifies the format without reading data."""
+        f = actfast.open(geneactiv_file)
+        assert f.format == "GeneActiv BIN"
+
+    def test_chunks_concatenate_to_read(self, geneactiv_file):
+        """Test that concatenated chunks equal a whole-file read."""
+        whole = actfast.read(geneactiv_file)["timeseries"]["high_frequency"]
+
+        chunks = list(actfast.open(geneactiv_file).iter_chunks(samples=1000))
+        assert len(chunks) > 1
+
+        for key in ("datetime", "acceleration", "light", "button_state"):
+            joined = np.concatenate([c["timeseries"]["high_frequency"][key] for c in chunks])
+            np.testing.assert_array_equal(joined, whole[key])
+
+    def test_chunks_metadata(self, actigraph_file):
+        """Test that metadata and warnings are available on the iterator."""
+        chunks = actfast.open(actigraph_file).iter_chunks(samples=600)
+        for chunk in chunks:
+            assert chunk["timeseries"]["acceleration"]["acceleration"].shape[1] == 3
+
+        assert "Sample Rate" in chunks.metadata["info"]
+        assert chunks.warnings == []
+
+    def test_open_unknown_format(self, tmp_path):
+        """Test that open() fails early for unknown formats."""
+        test_file = tmp_path / "unknown.bin"
+        test_file.write_bytes(b"UNKN" + b"\x00" * 100)
+
+        with pytest.raises(ValueError):
+            actfast.open(test_file)
+
+
 class TestReadErrors:
     """Tests for error handling."""
 
@@ -208,6 +461,53 @@ class TestReadErrors:
         assert "sqlite3" in error_msg  # Should suggest Python's sqlite3 module
 
 
+class TestExceptions:
+    """Tests for the typed exception hierarchy."""
+
+    def test_unknown_format(self, tmp_path):
+        """Test that an unknown format raises UnknownFormatError."""
+        test_file = tmp_path / "unknown.bin"
+        test_file.write_bytes(b"UNKN" + b"\x00" * 100)
+
+        with pytest.raises(actfast.UnknownFormatError) as exc_info:
+            actfast.read(test_file)
+
+        assert isinstance(exc_info.value, actfast.ActfastError)
+        assert isinstance(exc_info.value, ValueError)
+        assert exc_info.value.byte_offset is None
+
+    def test_unsupported_format(self, tmp_path):
+        """Test that a recognized but unsupported format raises UnsupportedFormatError."""
+        test_file = tmp_path / "audio.wav"
+        test_file.write_bytes(b"RIFF" + b"\x00" * 100)
+
+        with pytest.raises(actfast.UnsupportedFormatError):
+            actfast.read(test_file)
+
+    def test_unexpected_eof_location(self, tmp_path):
+        """Test that a truncated file reports where it ended."""
+        test_file = tmp_path / "truncated.bin"
+        test_file.write_bytes(b"Device Identity\nSerial:123\n")
+
+        with pytest.raises(actfast.UnexpectedEofError) as exc_info:
+            actfast.read(test_file)
+
+        assert exc_info.value.line_number is not None
+
+    def test_parse_error(self, tmp_path):
+        """Test that an invalid ZIP archive raises ParseError."""
+        test_file = tmp_path / "invalid.gt3x"
+        test_file.write_bytes(b"PK\x03\x04" + b"\x00" * 100)
+
+        with pytest.raises(actfast.ParseError):
+            actfast.read(test_file)
+
+    def test_file_not_found(self):
+        """Test that a missing file raises FileNotFoundError."""
+        with pytest.raises(FileNotFoundError):
+            actfast.read("/nonexistent/path/file.bin")
+
+
 class TestPathTypes:
     """Test different path input types."""
 
@@ -222,6 +522,96 @@ class TestPathTypes:
         assert result["format"] == "GeneActiv BIN"
 
 
+class TestInMemorySources:
+    """Test reading from bytes and file-like objects."""
+
+    def test_bytes(self, actigraph_file):
+        """Test that reading bytes matches reading the file."""
+        expected = actfast.read(actigraph_file)
+        result = actfast.read(actigraph_file.read_bytes())
+
+        assert result["format"] == expected["format"]
+        assert result["metadata"] == expected["metadata"]
+        np.testing.assert_array_equal(
+            result["timeseries"]["acceleration"]["acceleration"],
+            expected["timeseries"]["acceleration"]["acceleration"],
+        )
+
+    def test_bytearray(self, geneactiv_file):
+        """Test reading a bytearray."""
+        result = actfast.read(bytearray(geneactiv_file.read_bytes()))
+        assert result["format"] == "GeneActiv BIN"
+
+    def test_bytesio(self, geneactiv_file):
+        """Test reading an io.BytesIO."""
+        import io
+
+        expected = actfast.read(geneactiv_file)
+        result = actfast.read(io.BytesIO(geneactiv_file.read_bytes()))
+
+        np.testing.assert_array_equal(
+            result["timeseries"]["high_frequency"]["datetime"],
+            expected["timeseries"]["high_frequency"]["datetime"],
+        )
+
+    def test_open_file_object(self, actigraph_file):
+        """Test reading a file opened in binary mode."""
+        with open(actigraph_file, "rb") as f:
+            result = actfast.read(f)
+        assert result["format"] == "Actigraph GT3X"
+
+    def test_file_like_returning_bytes_like(self, actigraph_file):
+        """Test file-like objects whose read() returns a bytearray or memoryview."""
+        import io
+
+        class BytesLikeReader(io.BytesIO):
+            def __init__(self, data, wrap):
+                super().__init__(data)
+                self.wrap = wrap
+
+            def read(self, size=-1):
+                return self.wrap(super().read(size))
+
+        expected = actfast.read(actigraph_file)
+        for wrap in (bytearray, memoryview):
+            result = actfast.read(BytesLikeReader(actigraph_file.read_bytes(), wrap))
+
+            assert result["metadata"] == expected["metadata"]
+            np.testing.assert_array_equal(
+                result["timeseries"]["acceleration"]["acceleration"],
+                expected["timeseries"]["acceleration"]["acceleration"],
+            )
+
+    def test_unknown_bytes(self):
+        """Test that unrecognized bytes raise ValueError."""
+        with pytest.raises(ValueError, match="(?i)unknown"):
+            actfast.read(b"\x00\x01\x02\x03\x04\x05")
+
+    def test_invalid_type(self):
+        """Test that unsupported argument types raise TypeError."""
+        with pytest.raises(TypeError):
+            actfast.read(123)
+
+
+class TestConcurrency:
+    """Tests for reading from several Python threads."""
+
+    def test_threaded_reads_match(self, geneactiv_file, actigraph_file):
+        """Test that concurrent reads return the same data as sequential ones."""
+        from concurrent.futures import ThreadPoolExecutor
+
+        paths = [geneactiv_file, actigraph_file] * 4
+        expected = [actfast.read(p) for p in paths]
+        with ThreadPoolExecutor(max_workers=4) as pool:
+            results = list(pool.map(actfast.read, paths))
+
+        for result, reference in zip(results, expected):
+            assert result["metadata"] == reference["metadata"]
+            for name, table in reference["timeseries"].items():
+                for column, values in table.items():
+                    np.testing.assert_array_equal(result["timeseries"][name][column], values)
+
+
 class TestDataIntegrity:
     """Tests for data integrity and consistency."""
 
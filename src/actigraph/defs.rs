@@ -2,6 +2,8 @@
 
 pub const GT3X_FILE_INFO: &str = "info.txt";
 pub const GT3X_FILE_LOG: &str = "log.bin";
+pub const GT3X_FILE_ACTIVITY: &str = "activity.bin";
+pub const GT3X_FILE_LUX: &str = "lux.bin";
 
 #[derive(Debug)]
 pub enum LogRecordType {
@@ -0,0 +1,218 @@
+//! Activity counts computed on the device, logged as EPOCH records when
+//! recording in epoch mode (or with the `epoch_data` device feature).
+//! <https://github.com/actigraph/GT3X-File-Format/blob/main/LogRecords/Epoch.md>
+//!
+//! Each record holds one epoch starting at the record timestamp. The record
+//! versions differ in the width of the axis counts and in which of steps and
+//! inclinometer state follow them:
+//!
+//! | Record | Axis counts (axis 1, 2, 3) | Steps  | Inclinometer |
+//! |--------|----------------------------|--------|--------------|
+//! | EPOCH  | 3 x u16                    |        |              |
+//! | EPOCH2 | 3 x u16                    | u16    |              |
+//! | EPOCH3 | 3 x u16                    | u16    | u8           |
+//! | EPOCH4 | 3 x u32                    | u32    | u8           |
+//!
+//! All values are little-endian. Axis 1 is the vertical (Y) axis. The
+//! inclinometer state is 0 (off), 1 (standing), 2 (sitting) or 3 (lying).
+//! In files mixing record versions, epochs without steps or inclinometer
+//! state hold [`MISSING_STEPS`] and [`MISSING_INCLINOMETER`] in those columns.
+
+use super::defs::LogRecordType;
+use crate::sensors::{self, SensorKind};
+
+/// Channels stored in the `epoch_counts` table, in column order
+const EPOCH_SENSORS: [SensorKind; 3] = [
+    SensorKind::ActivityCounts,
+    SensorKind::Steps,
+    SensorKind::Inclinometer,
+];
+
+/// Steps of an epoch whose record version has none
+pub const MISSING_STEPS: u32 = u32::MAX;
+/// Inclinometer state of an epoch whose record version has none
+pub const MISSING_INCLINOMETER: u8 = u8::MAX;
+
+/// Field layout of an EPOCH record version
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochLayout {
+    /// Bytes per axis count (and step count)
+    value_size: usize,
+    steps: bool,
+    inclinometer: bool,
+}
+
+impl EpochLayout {
+    /// Layout of `record_type`, `None` if it is not an EPOCH record
+    pub fn of(record_type: &LogRecordType) -> Option<EpochLayout> {
+        let (value_size, steps, inclinometer) = match record_type {
+            LogRecordType::Epoch => (2, false, false),
+            LogRecordType::Epoch2 => (2, true, false),
+            LogRecordType::Epoch3 => (2, true, true),
+            LogRecordType::Epoch4 => (4, true, true),
+            _ => return None,
+        };
+        Some(EpochLayout {
+            value_size,
+            steps,
+            inclinometer,
+        })
+    }
+
+    fn value(&self, payload: &[u8], index: usize) -> Option<u32> {
+        let bytes = payload.get(index * self.value_size..(index + 1) * self.value_size)?;
+        Some(bytes.iter().rev().fold(0u32, |acc, &b| acc << 8 | b as u32))
+    }
+}
+
+/// A decoded epoch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Epoch {
+    pub counts: [u32; 3],
+    pub steps: Option<u32>,
+    pub inclinometer: Option<u8>,
+}
+
+impl Epoch {
+    /// Parse an EPOCH payload (without the checksum), `None` if it is too short
+    pub fn parse(layout: EpochLayout, payload: &[u8]) -> Option<Epoch> {
+        let counts = [
+            layout.value(payload, 0)?,
+            layout.value(payload, 1)?,
+            layout.value(payload, 2)?,
+        ];
+        let steps = if layout.steps {
+            Some(layout.value(payload, 3)?)
+        } else {
+            None
+        };
+        let inclinometer = if layout.inclinometer {
+            let offset = if layout.steps { 4 } else { 3 } * layout.value_size;
+            Some(*payload.get(offset)?)
+        } else {
+            None
+        };
+        Some(Epoch {
+            counts,
+            steps,
+            inclinometer,
+        })
+    }
+}
+
+/// Buffered epochs, moved into the `epoch_counts` table on emit
+pub struct EpochData {
+    pub time: Vec<i64>,
+    pub counts: Vec<u32>,
+    pub steps: Vec<u32>,
+    pub inclinometer: Vec<u8>,
+    /// Channels of the records seen so far, which become table columns
+    sensors: sensors::SensorSet,
+}
+
+impl Default for EpochData {
+    fn default() -> Self {
+        Self {
+            time: Vec::new(),
+            counts: Vec::new(),
+            steps: Vec::new(),
+            inclinometer: Vec::new(),
+            sensors: sensors::SensorSet::EMPTY,
+        }
+    }
+}
+
+impl EpochData {
+    /// Returns `true` once an EPOCH record has been seen
+    pub fn present(&self) -> bool {
+        self.sensors.contains(SensorKind::ActivityCounts)
+    }
+
+    /// Append an epoch. Channels missing from its record version are stored
+    /// as [`MISSING_STEPS`] and [`MISSING_INCLINOMETER`], so all columns keep
+    /// the same length.
+    pub fn push(&mut self, time: i64, epoch: &Epoch) {
+        self.sensors.insert(SensorKind::ActivityCounts);
+        if epoch.steps.is_some() {
+            self.sensors.insert(SensorKind::Steps);
+        }
+        if epoch.inclinometer.is_some() {
+            self.sensors.insert(SensorKind::Inclinometer);
+        }
+
+        self.time.push(time);
+        self.counts.extend(epoch.counts);
+        self.steps.push(epoch.steps.unwrap_or(MISSING_STEPS));
+        self.inclinometer
+            .push(epoch.inclinometer.unwrap_or(MISSING_INCLINOMETER));
+    }
+
+    /// Move the buffered epochs into an `epoch_counts` table
+    pub fn take_table(&mut self) -> sensors::SensorTable {
+        let mut counts = std::mem::take(&mut self.counts);
+        let mut steps = std::mem::take(&mut self.steps);
+        let mut inclinometer = std::mem::take(&mut self.inclinometer);
+        let data = EPOCH_SENSORS
+            .into_iter()
+            .filter(|&kind| self.sensors.contains(kind))
+            .map(|kind| sensors::SensorData {
+                kind,
+                data: match kind {
+                    SensorKind::ActivityCounts => {
+                        sensors::SensorDataDyn::U32(std::mem::take(&mut counts))
+                    }
+                    SensorKind::Steps => sensors::SensorDataDyn::U32(std::mem::take(&mut steps)),
+                    _ => sensors::SensorDataDyn::U8(std::mem::take(&mut inclinometer)),
+                },
+            })
+            .collect();
+        sensors::SensorTable {
+            name: "epoch_counts",
+            datetime: std::mem::take(&mut self.time),
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_epoch() {
+        let epoch3 = EpochLayout::of(&LogRecordType::Epoch3).unwrap();
+        let payload = [1, 0, 2, 0, 3, 1, 40, 0, 2];
+        assert_eq!(
+            Epoch::parse(epoch3, &payload),
+            Some(Epoch {
+                counts: [1, 2, 259],
+                steps: Some(40),
+                inclinometer: Some(2),
+            })
+        );
+        assert_eq!(Epoch::parse(epoch3, &payload[..8]), None);
+
+        let epoch = EpochLayout::of(&LogRecordType::Epoch).unwrap();
+        assert_eq!(
+            Epoch::parse(epoch, &payload),
+            Some(Epoch {
+                counts: [1, 2, 259],
+                steps: None,
+                inclinometer: None,
+            })
+        );
+
+        let epoch4 = EpochLayout::of(&LogRecordType::Epoch4).unwrap();
+        let payload = [0, 0, 1, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 1];
+        assert_eq!(
+            Epoch::parse(epoch4, &payload),
+            Some(Epoch {
+                counts: [65536, 2, 3],
+                steps: Some(4),
+                inclinometer: Some(1),
+            })
+        );
+
+        assert_eq!(EpochLayout::of(&LogRecordType::Activity), None);
+    }
+}
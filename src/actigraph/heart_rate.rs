@@ -0,0 +1,229 @@
+//! Heart rate from paired monitors, logged as HEART_RATE_BPM, HEART_RATE_ANT
+//! and HEART_RATE_BLE records.
+//! <https://github.com/actigraph/GT3X-File-Format/blob/main/LogRecords/HeartRateBPM.md>
+//! <https://github.com/actigraph/GT3X-File-Format/blob/main/LogRecords/HeartRateANT.md>
+//! <https://github.com/actigraph/GT3X-File-Format/blob/main/LogRecords/HeartRateBLE.md>
+//!
+//! The `heart_rate` table has one row per RR interval, all at the time of the
+//! record they came with, and one row with a NaN interval for records without
+//! RR intervals.
+
+use crate::sensors::{self, SensorKind};
+
+/// Heart beat event times of ANT+ and BLE monitors are in 1/1024 s
+const BEAT_TIME_UNITS_PER_SECOND: f32 = 1024.0;
+
+/// Channels stored in the `heart_rate` table, in column order
+const HEART_RATE_SENSORS: [SensorKind; 2] = [SensorKind::HeartRate, SensorKind::RrInterval];
+
+/// A decoded heart rate record
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeartRate {
+    /// Beats per minute
+    pub bpm: u16,
+    /// RR intervals in seconds
+    pub rr_intervals: Vec<f32>,
+}
+
+/// Parse a HEART_RATE_BPM payload, a single unsigned byte
+pub fn parse_bpm(payload: &[u8]) -> Option<HeartRate> {
+    Some(HeartRate {
+        bpm: *payload.first()? as u16,
+        rr_intervals: Vec::new(),
+    })
+}
+
+/// Parse a HEART_RATE_BLE payload, the Heart Rate Measurement characteristic
+/// of the Bluetooth heart rate profile
+pub fn parse_ble(payload: &[u8]) -> Option<HeartRate> {
+    let (&flags, mut rest) = payload.split_first()?;
+    let bpm = if flags & 0x01 != 0 {
+        let bpm = u16::from_le_bytes([*rest.first()?, *rest.get(1)?]);
+        rest = &rest[2..];
+        bpm
+    } else {
+        let bpm = *rest.first()? as u16;
+        rest = &rest[1..];
+        bpm
+    };
+    // Energy expended
+    if flags & 0x08 != 0 {
+        rest = rest.get(2..)?;
+    }
+    let rr_intervals = if flags & 0x10 != 0 {
+        rest.chunks_exact(2)
+            .map(|rr| u16::from_le_bytes([rr[0], rr[1]]) as f32 / BEAT_TIME_UNITS_PER_SECOND)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Some(HeartRate { bpm, rr_intervals })
+}
+
+/// Decoder of HEART_RATE_ANT payloads, which hold an ANT+ heart rate monitor
+/// data page. RR intervals come from the event times of consecutive beats,
+/// so the last beat is kept across records.
+#[derive(Debug, Default)]
+pub struct AntDecoder {
+    /// Event time and count of the last beat
+    last_beat: Option<(u16, u8)>,
+}
+
+impl AntDecoder {
+    pub fn parse(&mut self, payload: &[u8]) -> Option<HeartRate> {
+        let page = payload.get(0..8)?;
+        let event_time = u16::from_le_bytes([page[4], page[5]]);
+        let count = page[6];
+        let bpm = page[7] as u16;
+
+        let previous_event_time = match self.last_beat {
+            // The same beat, repeated in the next page
+            Some((_, last_count)) if last_count == count => {
+                return Some(HeartRate {
+                    bpm,
+                    rr_intervals: Vec::new(),
+                });
+            }
+            // Page 4 carries the event time of the previous beat
+            _ if page[0] & 0x7F == 4 => Some(u16::from_le_bytes([page[2], page[3]])),
+            Some((last_time, last_count)) if last_count.wrapping_add(1) == count => Some(last_time),
+            _ => None,
+        };
+        self.last_beat = Some((event_time, count));
+
+        let rr_intervals = previous_event_time
+            .map(|previous| event_time.wrapping_sub(previous) as f32 / BEAT_TIME_UNITS_PER_SECOND)
+            .into_iter()
+            .collect();
+        Some(HeartRate { bpm, rr_intervals })
+    }
+}
+
+/// Buffered heart rate rows, moved into the `heart_rate` table on emit
+#[derive(Default)]
+pub struct HeartRateData {
+    pub time: Vec<i64>,
+    pub bpm: Vec<u16>,
+    pub rr_interval: Vec<f32>,
+    /// Set once a heart rate record has been seen
+    present: bool,
+}
+
+impl HeartRateData {
+    /// Returns `true` once a heart rate record has been seen
+    pub fn present(&self) -> bool {
+        self.present
+    }
+
+    pub fn push(&mut self, time: i64, heart_rate: &HeartRate) {
+        self.present = true;
+        if heart_rate.rr_intervals.is_empty() {
+            self.time.push(time);
+            self.bpm.push(heart_rate.bpm);
+            self.rr_interval.push(f32::NAN);
+        }
+        for &rr in &heart_rate.rr_intervals {
+            self.time.push(time);
+            self.bpm.push(heart_rate.bpm);
+            self.rr_interval.push(rr);
+        }
+    }
+
+    /// Move the buffered rows into a `heart_rate` table
+    pub fn take_table(&mut self) -> sensors::SensorTable {
+        let [heart_rate, rr_interval] = HEART_RATE_SENSORS;
+        sensors::SensorTable {
+            name: "heart_rate",
+            datetime: std::mem::take(&mut self.time),
+            data: vec![
+                sensors::SensorData {
+                    kind: heart_rate,
+                    data: sensors::SensorDataDyn::U16(std::mem::take(&mut self.bpm)),
+                },
+                sensors::SensorData {
+                    kind: rr_interval,
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.rr_interval)),
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ble() {
+        // 8-bit heart rate without RR intervals
+        assert_eq!(
+            parse_ble(&[0x00, 72]),
+            Some(HeartRate {
+                bpm: 72,
+                rr_intervals: vec![]
+            })
+        );
+        // 16-bit heart rate, energy expended and two RR intervals
+        assert_eq!(
+            parse_ble(&[0x19, 60, 0, 0x10, 0x00, 0x00, 0x04, 0x00, 0x02]),
+            Some(HeartRate {
+                bpm: 60,
+                rr_intervals: vec![1.0, 0.5]
+            })
+        );
+        assert_eq!(parse_ble(&[0x01, 60]), None);
+        assert_eq!(parse_ble(&[]), None);
+    }
+
+    #[test]
+    fn test_ant_decoder() {
+        let page = |number: u8, previous: u16, time: u16, count: u8, bpm: u8| {
+            let [p0, p1] = previous.to_le_bytes();
+            let [t0, t1] = time.to_le_bytes();
+            [number, 0, p0, p1, t0, t1, count, bpm]
+        };
+        let mut decoder = AntDecoder::default();
+
+        // First beat of a page without the previous event time
+        let hr = decoder.parse(&page(0, 0, 1024, 1, 60)).unwrap();
+        assert_eq!(hr.bpm, 60);
+        assert!(hr.rr_intervals.is_empty());
+        // Next beat
+        let hr = decoder.parse(&page(0x80, 0, 1536, 2, 61)).unwrap();
+        assert_eq!(hr.rr_intervals, [0.5]);
+        // Same beat again
+        let hr = decoder.parse(&page(0, 0, 1536, 2, 61)).unwrap();
+        assert!(hr.rr_intervals.is_empty());
+        // Missed beat, but page 4 carries the previous event time (wrapping)
+        let hr = decoder.parse(&page(4, 65024, 512, 5, 62)).unwrap();
+        assert_eq!(hr.rr_intervals, [1.0]);
+
+        assert!(decoder.parse(&[0; 7]).is_none());
+    }
+
+    #[test]
+    fn test_heart_rate_rows() {
+        let mut data = HeartRateData::default();
+        assert!(!data.present());
+        data.push(1, &parse_bpm(&[70]).unwrap());
+        data.push(
+            2,
+            &HeartRate {
+                bpm: 71,
+                rr_intervals: vec![0.8, 0.9],
+            },
+        );
+        let table = data.take_table();
+        assert_eq!(table.datetime, [1, 2, 2]);
+        assert_eq!(
+            table.data[0].data,
+            sensors::SensorDataDyn::U16(vec![70, 71, 71])
+        );
+        let sensors::SensorDataDyn::F32(rr) = &table.data[1].data else {
+            panic!("Expected F32 data");
+        };
+        assert!(rr[0].is_nan());
+        assert_eq!(rr[1..], [0.8, 0.9]);
+        assert!(data.present());
+    }
+}
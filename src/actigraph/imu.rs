@@ -0,0 +1,421 @@
+//! IMU data of GT9X devices, logged as SENSOR_DATA records whose layout is
+//! described by a preceding SENSOR_SCHEMA record.
+//! <https://github.com/actigraph/GT3X-File-Format/blob/main/LogRecords/SensorSchema.md>
+//! <https://github.com/actigraph/GT3X-File-Format/blob/main/LogRecords/SensorData.md>
+
+use super::ssp_codec;
+use crate::sensors::{self, SensorKind};
+
+/// Size of a column descriptor in a SENSOR_SCHEMA record
+const COLUMN_DESCRIPTOR_SIZE: usize = 23;
+/// Size of the SENSOR_SCHEMA header (id, column count, samples per record)
+const SCHEMA_HEADER_SIZE: usize = 6;
+
+/// IMU channels stored in the `imu` table, in column order
+const IMU_SENSORS: [SensorKind; 3] = [
+    SensorKind::Gyroscope,
+    SensorKind::Magnetometer,
+    SensorKind::Temperature,
+];
+
+/// Values per sample of an IMU sensor
+fn axes(kind: SensorKind) -> usize {
+    match kind {
+        SensorKind::Temperature => 1,
+        _ => 3,
+    }
+}
+
+/// Scales from the `Parameters` record, raw values are divided by them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImuScales {
+    pub gyroscope: Option<f64>,
+    pub magnetometer: Option<f64>,
+    pub temperature: Option<f64>,
+    /// Added to the scaled temperature
+    pub temperature_offset: Option<f64>,
+}
+
+impl ImuScales {
+    fn scale(&self, kind: SensorKind) -> Option<f64> {
+        match kind {
+            SensorKind::Gyroscope => self.gyroscope,
+            SensorKind::Magnetometer => self.magnetometer,
+            SensorKind::Temperature => self.temperature,
+            _ => None,
+        }
+        .filter(|&scale| scale != 0.0)
+    }
+}
+
+#[derive(Debug)]
+struct Column {
+    big_endian: bool,
+    signed: bool,
+    bit_offset: usize,
+    bit_size: usize,
+    /// Scale from the schema, used if the parameters define none
+    scale: f64,
+    /// `None` for channels not decoded (such as the IMU accelerometer)
+    kind: Option<SensorKind>,
+}
+
+impl Column {
+    /// Read the raw value of this column from a sample
+    fn read(&self, sample: &[u8]) -> Option<i64> {
+        if self.bit_size == 0 || self.bit_size > 32 {
+            return None;
+        }
+        let start = self.bit_offset / 8;
+        let end = (self.bit_offset + self.bit_size).div_ceil(8);
+        let bytes = sample.get(start..end)?;
+
+        let raw = if self.big_endian {
+            let raw = bytes.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+            raw >> (end * 8 - self.bit_offset - self.bit_size)
+        } else {
+            let raw = bytes.iter().rev().fold(0u64, |acc, &b| acc << 8 | b as u64);
+            raw >> (self.bit_offset % 8)
+        };
+        let raw = raw & ((1u64 << self.bit_size) - 1);
+
+        let shift = 64 - self.bit_size;
+        Some(if self.signed {
+            ((raw << shift) as i64) >> shift
+        } else {
+            raw as i64
+        })
+    }
+}
+
+/// Map a column label such as "Gyroscope X" to the sensor it belongs to
+fn column_kind(label: &str) -> Option<SensorKind> {
+    let label = label.to_ascii_lowercase();
+    if label.contains("gyro") {
+        Some(SensorKind::Gyroscope)
+    } else if label.contains("mag") {
+        Some(SensorKind::Magnetometer)
+    } else if label.contains("temp") {
+        Some(SensorKind::Temperature)
+    } else {
+        None
+    }
+}
+
+/// Layout of the samples in SENSOR_DATA records with the same id
+#[derive(Debug)]
+pub struct SensorSchema {
+    pub id: u16,
+    /// Samples per record, i.e. per second
+    samples_per_record: u16,
+    columns: Vec<Column>,
+    /// Bytes per sample
+    sample_size: usize,
+}
+
+impl SensorSchema {
+    /// Parse a SENSOR_SCHEMA payload (without the checksum)
+    pub fn parse(payload: &[u8]) -> Option<SensorSchema> {
+        let header = payload.get(0..SCHEMA_HEADER_SIZE)?;
+        let id = u16::from_le_bytes([header[0], header[1]]);
+        let column_count = u16::from_le_bytes([header[2], header[3]]) as usize;
+        let samples_per_record = u16::from_le_bytes([header[4], header[5]]);
+
+        let descriptors = payload
+            .get(SCHEMA_HEADER_SIZE..SCHEMA_HEADER_SIZE + column_count * COLUMN_DESCRIPTOR_SIZE)?;
+        let columns: Vec<Column> = descriptors
+            .chunks_exact(COLUMN_DESCRIPTOR_SIZE)
+            .map(|d| {
+                let label = String::from_utf8_lossy(&d[7..23]);
+                Column {
+                    big_endian: d[0] & 1 != 0,
+                    signed: d[0] & 2 != 0,
+                    bit_offset: d[1] as usize,
+                    bit_size: d[2] as usize,
+                    scale: ssp_codec::decode(u32::from_le_bytes([d[3], d[4], d[5], d[6]])),
+                    kind: column_kind(label.trim_end_matches(['\0', ' '])),
+                }
+            })
+            .collect();
+
+        let sample_size = columns
+            .iter()
+            .map(|c| (c.bit_offset + c.bit_size).div_ceil(8))
+            .max()
+            .unwrap_or(0);
+
+        Some(SensorSchema {
+            id,
+            samples_per_record,
+            columns,
+            sample_size,
+        })
+    }
+
+    /// IMU sensors with at least one column in this schema
+    pub fn sensors(&self) -> impl Iterator<Item = SensorKind> + '_ {
+        IMU_SENSORS
+            .into_iter()
+            .filter(|&kind| self.columns.iter().any(|c| c.kind == Some(kind)))
+    }
+}
+
+/// Decoded IMU samples, moved into the `imu` table on emit
+pub struct ImuData {
+    pub time: Vec<i64>,
+    pub gyroscope: Vec<f32>,
+    pub magnetometer: Vec<f32>,
+    pub temperature: Vec<f32>,
+    /// Selected IMU sensors of the schemas seen so far, which become table
+    /// columns
+    sensors: sensors::SensorSet,
+}
+
+impl Default for ImuData {
+    fn default() -> Self {
+        Self {
+            time: Vec::new(),
+            gyroscope: Vec::new(),
+            magnetometer: Vec::new(),
+            temperature: Vec::new(),
+            sensors: sensors::SensorSet::EMPTY,
+        }
+    }
+}
+
+impl ImuData {
+    /// Returns `true` once a schema with IMU columns has been seen
+    pub fn present(&self) -> bool {
+        IMU_SENSORS.iter().any(|&kind| self.sensors.contains(kind))
+    }
+
+    /// Add the columns of `schema` that are in `selected`
+    pub fn add_schema(&mut self, schema: &SensorSchema, selected: sensors::SensorSet) {
+        for kind in schema.sensors() {
+            if selected.contains(kind) && !self.sensors.contains(kind) {
+                self.sensors.insert(kind);
+                // Samples buffered before this schema lack the new column.
+                let samples = self.time.len();
+                self.values_mut(kind).resize(samples * axes(kind), f32::NAN);
+            }
+        }
+    }
+
+    fn values_mut(&mut self, kind: SensorKind) -> &mut Vec<f32> {
+        match kind {
+            SensorKind::Gyroscope => &mut self.gyroscope,
+            SensorKind::Magnetometer => &mut self.magnetometer,
+            _ => &mut self.temperature,
+        }
+    }
+
+    /// Decode the samples of a SENSOR_DATA payload (after the schema id)
+    /// starting at `record_time`, dropping those outside the time window.
+    /// Every column gets a value per sample: sensors the schema lacks (or
+    /// axes beyond its columns) are NaN.
+    pub fn push_samples(
+        &mut self,
+        schema: &SensorSchema,
+        scales: &ImuScales,
+        options: &sensors::ReadOptions,
+        record_time: i64,
+        payload: &[u8],
+    ) {
+        if schema.sample_size == 0 || !schema.sensors().any(|kind| self.sensors.contains(kind)) {
+            return;
+        }
+        let samples = payload.chunks_exact(schema.sample_size);
+        let samples_per_second = match schema.samples_per_record {
+            0 => samples.len().max(1),
+            n => n as usize,
+        };
+        let step = 1_000_000_000 / samples_per_second as i64;
+
+        for (i, sample) in samples.enumerate() {
+            let time = record_time + step * i as i64;
+            if !options.in_window(time) {
+                continue;
+            }
+            self.time.push(time);
+
+            for kind in IMU_SENSORS {
+                if !self.sensors.contains(kind) {
+                    continue;
+                }
+                let offset = match kind {
+                    SensorKind::Temperature => scales.temperature_offset.unwrap_or(0.0),
+                    _ => 0.0,
+                };
+                let values = schema
+                    .columns
+                    .iter()
+                    .filter(|column| column.kind == Some(kind))
+                    .map(|column| {
+                        let scale = scales
+                            .scale(kind)
+                            .or((column.scale != 0.0).then_some(column.scale))
+                            .unwrap_or(1.0);
+                        let raw = column.read(sample).unwrap_or_default();
+                        (raw as f64 / scale + offset) as f32
+                    });
+                let buffer = self.values_mut(kind);
+                let end = buffer.len() + axes(kind);
+                buffer.extend(values.take(axes(kind)));
+                buffer.resize(end, f32::NAN);
+            }
+        }
+    }
+
+    /// Move the buffered samples into an `imu` table
+    pub fn take_table(&mut self) -> sensors::SensorTable {
+        let selected = self.sensors;
+        let data = IMU_SENSORS
+            .into_iter()
+            .filter(|&kind| selected.contains(kind))
+            .map(|kind| sensors::SensorData {
+                kind,
+                data: sensors::SensorDataDyn::F32(std::mem::take(self.values_mut(kind))),
+            })
+            .collect();
+        sensors::SensorTable {
+            name: "imu",
+            datetime: std::mem::take(&mut self.time),
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(flags: u8, bit_offset: u8, bit_size: u8, label: &str) -> Vec<u8> {
+        let mut d = vec![flags, bit_offset, bit_size];
+        d.extend(ssp_codec::encode(1.0).to_le_bytes());
+        let mut label = label.as_bytes().to_vec();
+        label.resize(16, 0);
+        d.extend(label);
+        d
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        let mut payload = vec![7, 0, 3, 0, 100, 0];
+        payload.extend(descriptor(2, 0, 16, "IMU Accel X"));
+        payload.extend(descriptor(2, 16, 16, "Gyroscope X"));
+        payload.extend(descriptor(3, 32, 12, "Temperature"));
+        let schema = SensorSchema::parse(&payload).unwrap();
+
+        assert_eq!(schema.id, 7);
+        assert_eq!(schema.samples_per_record, 100);
+        assert_eq!(schema.sample_size, 6);
+        assert_eq!(
+            schema.sensors().collect::<Vec<_>>(),
+            [SensorKind::Gyroscope, SensorKind::Temperature]
+        );
+
+        // Truncated descriptors
+        assert!(SensorSchema::parse(&payload[..payload.len() - 1]).is_none());
+    }
+
+    /// Schema `id` of one sample per second with a signed 16-bit column per label
+    fn schema(id: u8, labels: &[&str]) -> SensorSchema {
+        let mut payload = vec![id, 0, labels.len() as u8, 0, 1, 0];
+        for (i, label) in labels.iter().enumerate() {
+            payload.extend(descriptor(2, i as u8 * 16, 16, label));
+        }
+        SensorSchema::parse(&payload).unwrap()
+    }
+
+    fn column_values(table: &sensors::SensorTable, kind: SensorKind) -> Option<&Vec<f32>> {
+        table
+            .data
+            .iter()
+            .find(|d| d.kind == kind)
+            .map(|d| match &d.data {
+                sensors::SensorDataDyn::F32(values) => values,
+                _ => panic!("Expected F32 data"),
+            })
+    }
+
+    #[test]
+    fn test_mixed_schemas() {
+        let gyroscope = schema(1, &["Gyroscope X", "Gyroscope Y", "Gyroscope Z"]);
+        let magnetometer = schema(2, &["Magnetometer X", "Magnetometer Y", "Temperature"]);
+        let scales = ImuScales::default();
+        let options = sensors::ReadOptions::default();
+        let sample = [1, 0, 2, 0, 3, 0];
+
+        let mut imu = ImuData::default();
+        imu.add_schema(&gyroscope, options.sensors);
+        imu.push_samples(&gyroscope, &scales, &options, 0, &sample);
+        imu.add_schema(&magnetometer, options.sensors);
+        imu.push_samples(&magnetometer, &scales, &options, 1, &sample);
+        imu.push_samples(&gyroscope, &scales, &options, 2, &sample);
+        let table = imu.take_table();
+
+        assert_eq!(table.datetime, [0, 1, 2]);
+        let nan = f32::NAN;
+        let same = |values: &[f32], expected: &[f32]| {
+            values.len() == expected.len()
+                && values
+                    .iter()
+                    .zip(expected)
+                    .all(|(a, b)| a == b || a.is_nan() && b.is_nan())
+        };
+        let gyro = column_values(&table, SensorKind::Gyroscope).unwrap();
+        assert!(same(gyro, &[1.0, 2.0, 3.0, nan, nan, nan, 1.0, 2.0, 3.0]));
+        // Only two magnetometer axes in the schema
+        let mag = column_values(&table, SensorKind::Magnetometer).unwrap();
+        assert!(same(mag, &[nan, nan, nan, 1.0, 2.0, nan, nan, nan, nan]));
+        let temperature = column_values(&table, SensorKind::Temperature).unwrap();
+        assert!(same(temperature, &[nan, 3.0, nan]));
+    }
+
+    #[test]
+    fn test_selected_sensors() {
+        let gyroscope = schema(1, &["Gyroscope X", "Gyroscope Y", "Gyroscope Z"]);
+        let magnetometer = schema(2, &["Magnetometer X", "Magnetometer Y", "Magnetometer Z"]);
+        let scales = ImuScales::default();
+        let options = sensors::ReadOptions {
+            sensors: [SensorKind::Magnetometer].into_iter().collect(),
+            ..Default::default()
+        };
+        let sample = [1, 0, 2, 0, 3, 0];
+
+        let mut imu = ImuData::default();
+        imu.add_schema(&gyroscope, options.sensors);
+        assert!(!imu.present());
+        imu.add_schema(&magnetometer, options.sensors);
+        imu.push_samples(&gyroscope, &scales, &options, 0, &sample);
+        imu.push_samples(&magnetometer, &scales, &options, 1, &sample);
+        let table = imu.take_table();
+
+        assert_eq!(table.datetime, [1]);
+        assert_eq!(table.data.len(), 1);
+        assert_eq!(
+            column_values(&table, SensorKind::Magnetometer).unwrap(),
+            &[1.0, 2.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn test_column_read() {
+        let column = |big_endian, signed, bit_offset, bit_size| Column {
+            big_endian,
+            signed,
+            bit_offset,
+            bit_size,
+            scale: 1.0,
+            kind: None,
+        };
+        let sample = [0xFE, 0xFF, 0x12, 0x34];
+
+        assert_eq!(column(false, true, 0, 16).read(&sample), Some(-2));
+        assert_eq!(column(false, false, 0, 16).read(&sample), Some(0xFFFE));
+        assert_eq!(column(true, false, 16, 16).read(&sample), Some(0x1234));
+        assert_eq!(column(true, false, 16, 12).read(&sample), Some(0x123));
+        assert_eq!(column(false, false, 20, 12).read(&sample), Some(0x341));
+        assert_eq!(column(false, false, 24, 16).read(&sample), None);
+    }
+}
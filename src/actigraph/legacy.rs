@@ -0,0 +1,189 @@
+//! Legacy GT3X layout of older devices and firmware, which store the samples
+//! in `activity.bin` and `lux.bin` instead of `log.bin`.
+//! <https://github.com/actigraph/NHANES-GT3X-File-Format>
+//!
+//! `activity.bin` is a continuous stream of samples packed like `Activity`
+//! records (12-bit Y, X, Z), starting at the `info.txt` start date at its
+//! sample rate. `lux.bin` holds one little-endian 16-bit value per second.
+
+use std::io::Read;
+
+use super::defs::{GT3X_FILE_ACTIVITY, GT3X_FILE_LOG, GT3X_FILE_LUX};
+use super::{
+    ActigraphReader, InfoTxt, NANOS_PER_SECOND, activity_samples, archive_file, read_full,
+    ticks_to_nanos,
+};
+use crate::error::{ActfastError, FileLocation, IoResultExt, Result};
+use crate::sensors::{self, SensorKind};
+
+/// Acceleration scale of GT3X devices, used if `info.txt` has none
+pub const DEFAULT_ACCEL_SCALE: f32 = 341.0;
+
+/// Bytes of two packed samples, the smallest byte-aligned unit
+const SAMPLE_PAIR_SIZE: usize = 9;
+
+/// Returns `true` if the archive has the legacy layout
+pub fn is_legacy<R: Read + std::io::Seek>(archive: &zip::ZipArchive<R>) -> bool {
+    archive.index_for_name(GT3X_FILE_LOG).is_none()
+        && archive.index_for_name(GT3X_FILE_ACTIVITY).is_some()
+}
+
+/// Fail in strict mode, otherwise keep `error` as a warning
+fn tolerate(error: ActfastError, result: &mut sensors::ReadResult, lenient: bool) -> Result<()> {
+    if !lenient {
+        return Err(error);
+    }
+    result.warnings.push(error.to_string());
+    Ok(())
+}
+
+/// Error for `file` ending inside a value at `byte_offset`
+fn truncated(file: &str, byte_offset: u64) -> ActfastError {
+    ActfastError::UnexpectedEof {
+        context: format!("in {} (incomplete sample)", file),
+        location: FileLocation {
+            byte_offset: Some(byte_offset),
+            ..FileLocation::new()
+        },
+    }
+}
+
+/// Time of sample `index` of a stream starting at `start`, with the same
+/// per-second spacing as `log.bin` activity records
+fn sample_time(start: i64, sample_rate: u32, index: u64) -> i64 {
+    let second = (index / sample_rate as u64) as i64;
+    let within = (index % sample_rate as u64) as i64;
+    start + second * NANOS_PER_SECOND + within * (NANOS_PER_SECOND / sample_rate as i64)
+}
+
+impl ActigraphReader {
+    /// Read `activity.bin` and `lux.bin` of a legacy archive. The light
+    /// values are small enough to load up front, which keeps chunks
+    /// time-ordered.
+    ///
+    /// In lenient mode, a read error or a file ending inside a sample keeps
+    /// the samples before it with a warning. The stream has no timestamps
+    /// to find idle sleep gaps by, so [`sensors::ReadOptions::idle_sleep`]
+    /// does not apply.
+    pub(super) fn read_legacy<R, S>(
+        &mut self,
+        archive: &mut zip::ZipArchive<R>,
+        header: &InfoTxt,
+        sensor_table_callback: &mut S,
+        result: &mut sensors::ReadResult,
+        lenient: bool,
+    ) -> Result<()>
+    where
+        R: Read + std::io::Seek,
+        S: FnMut(Vec<sensors::SensorTable>),
+    {
+        let selected = self.options.sensors;
+        let start = ticks_to_nanos(header.date_start).unwrap_or_default();
+        let sample_rate = (header.sample_rate as u32).max(1);
+        let accel_scale = header.accel_scale.unwrap_or(DEFAULT_ACCEL_SCALE);
+
+        let mut lux = Vec::new();
+        if selected.contains(SensorKind::Light)
+            && let Ok(file) = archive_file(archive, GT3X_FILE_LUX)
+        {
+            // On a read error, the bytes read so far are kept.
+            let mut bytes = Vec::new();
+            if let Err(error) = std::io::BufReader::new(file)
+                .read_to_end(&mut bytes)
+                .with_context(format!("reading {}", GT3X_FILE_LUX))
+            {
+                tolerate(error, result, lenient)?;
+                bytes.truncate(bytes.len() - bytes.len() % 2);
+            }
+            if bytes.len() % 2 != 0 {
+                let error = truncated(GT3X_FILE_LUX, bytes.len() as u64);
+                tolerate(error, result, lenient)?;
+            }
+            lux = bytes
+                .chunks_exact(2)
+                .map(|value| u16::from_le_bytes([value[0], value[1]]))
+                .collect();
+        }
+        let mut lux = lux
+            .into_iter()
+            .enumerate()
+            .map(|(second, value)| (start + second as i64 * NANOS_PER_SECOND, value))
+            .filter(|&(time, _)| self.options.in_window(time))
+            .peekable();
+
+        if selected.contains(SensorKind::Accelerometer) {
+            let mut activity = archive_file(archive, GT3X_FILE_ACTIVITY)?;
+            let mut block = vec![0u8; SAMPLE_PAIR_SIZE * 4096];
+            let mut index = 0u64;
+            let mut byte_offset = 0u64;
+            loop {
+                let len = match read_full(&mut activity, &mut block)
+                    .with_context(format!("reading {}", GT3X_FILE_ACTIVITY))
+                {
+                    Ok(len) => len,
+                    Err(error) => {
+                        tolerate(error, result, lenient)?;
+                        break;
+                    }
+                };
+                byte_offset += len as u64;
+                for [x, y, z] in activity_samples(&block[..len]) {
+                    let time = sample_time(start, sample_rate, index);
+                    index += 1;
+                    if self.options.in_window(time) {
+                        self.data.acceleration_time.push(time);
+                        self.data.acceleration.extend([
+                            x as f32 / accel_scale,
+                            y as f32 / accel_scale,
+                            z as f32 / accel_scale,
+                        ]);
+                    }
+                }
+
+                let next_time = sample_time(start, sample_rate, index);
+                while let Some((time, value)) = lux.next_if(|&(time, _)| time < next_time) {
+                    self.data.lux_time.push(time);
+                    self.data.lux.push(value);
+                }
+                if self.options.after_window(next_time) {
+                    break;
+                }
+                if len < block.len() {
+                    // An odd number of samples leaves 4 bits of padding, a
+                    // whole byte or more is a cut off sample.
+                    if len * 8 % 36 >= 8 {
+                        tolerate(truncated(GT3X_FILE_ACTIVITY, byte_offset), result, lenient)?;
+                    }
+                    break;
+                }
+                if self.options.chunk_full(self.data.buffered_len()) {
+                    self.data
+                        .emit_sensor_tables(selected, sensor_table_callback);
+                    if self.options.cancelled() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        for (time, value) in lux {
+            self.data.lux_time.push(time);
+            self.data.lux.push(value);
+        }
+        self.data
+            .emit_sensor_tables(selected, sensor_table_callback);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_time() {
+        assert_eq!(sample_time(0, 30, 0), 0);
+        assert_eq!(sample_time(0, 30, 29), 29 * 33_333_333);
+        assert_eq!(sample_time(5, 30, 30), 5 + NANOS_PER_SECOND);
+    }
+}
@@ -1,13 +1,26 @@
 mod defs;
+mod epoch;
+mod heart_rate;
+mod imu;
+mod legacy;
 mod ssp_codec;
 
 use crate::error::{ActfastError, FileLocation, Result};
 use crate::file_format::FileFormat;
+use crate::sensors::MetadataValue;
 use crate::{actigraph::defs::*, sensors};
 use bitreader::BitReader;
-use chrono::{TimeDelta, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::{HashMap, VecDeque};
 use std::io::{BufRead, BufReader, Read};
 
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+/// Time after the first sample in which [`ActigraphReader::info`] looks for
+/// light, capsense and battery records. Devices log battery records every
+/// minute, the others at least as often where the sensor exists.
+const INFO_SCAN_NANOS: i64 = 10 * 60 * NANOS_PER_SECOND;
+
 fn datetime_add_hz(
     dt: chrono::DateTime<Utc>,
     hz: u32,
@@ -32,6 +45,10 @@ pub struct AccelerometerData {
 
     pub battery_voltage_time: Vec<i64>,
     pub battery_voltage: Vec<u16>,
+
+    pub imu: imu::ImuData,
+    pub epochs: epoch::EpochData,
+    pub heart_rate: heart_rate::HeartRateData,
 }
 
 impl AccelerometerData {
@@ -80,6 +97,148 @@ impl AccelerometerData {
     pub fn reserve_default(&mut self) {
         self.reserve(200_000_000, 50_000_000);
     }
+
+    /// Append the samples of an activity record starting at `dt`, dropping
+    /// those outside the time window. Returns the last sample of the record.
+    fn push_activity(
+        &mut self,
+        options: &sensors::ReadOptions,
+        dt: chrono::DateTime<Utc>,
+        sample_rate: u32,
+        accel_scale: f32,
+        samples: impl Iterator<Item = [i16; 3]>,
+    ) -> Option<[f32; 3]> {
+        let mut last = None;
+        for (i, [x, y, z]) in samples.enumerate() {
+            let sample = [
+                x as f32 / accel_scale,
+                y as f32 / accel_scale,
+                z as f32 / accel_scale,
+            ];
+            last = Some(sample);
+
+            let timestamp_nanos = datetime_add_hz(dt, sample_rate, i as u32)
+                .timestamp_nanos_opt()
+                .unwrap_or_default();
+
+            if !options.in_window(timestamp_nanos) {
+                continue;
+            }
+
+            self.acceleration_time.push(timestamp_nanos);
+            self.acceleration.extend(&sample);
+        }
+        last
+    }
+
+    /// Fill the whole seconds in `[from, to)` with `sample_rate` copies of
+    /// `sample` per second, dropping those outside the time window
+    fn fill_idle(
+        &mut self,
+        options: &sensors::ReadOptions,
+        from: i64,
+        to: i64,
+        sample_rate: u32,
+        sample: [f32; 3],
+    ) {
+        let step = (1_000_000_000 / sample_rate.max(1)) as i64;
+        for second in (from..to).step_by(NANOS_PER_SECOND as usize) {
+            if !options.overlaps_window(second, second + NANOS_PER_SECOND) {
+                continue;
+            }
+            for i in 0..sample_rate as i64 {
+                let timestamp_nanos = second + step * i;
+                if options.in_window(timestamp_nanos) {
+                    self.acceleration_time.push(timestamp_nanos);
+                    self.acceleration.extend(&sample);
+                }
+            }
+        }
+    }
+
+    /// Length of the longest buffered table, used to decide when to flush a chunk.
+    fn buffered_len(&self) -> usize {
+        self.acceleration_time
+            .len()
+            .max(self.lux_time.len())
+            .max(self.capsense_time.len())
+            .max(self.battery_voltage_time.len())
+            .max(self.imu.time.len())
+            .max(self.epochs.time.len())
+            .max(self.heart_rate.time.len())
+    }
+
+    fn emit_sensor_tables<S: FnMut(Vec<sensors::SensorTable>)>(
+        &mut self,
+        selected: sensors::SensorSet,
+        sensor_table_callback: &mut S,
+    ) {
+        let tables = [
+            sensors::SensorTable {
+                name: sensors::SensorKind::Accelerometer.as_str(),
+                datetime: std::mem::take(&mut self.acceleration_time),
+                data: vec![sensors::SensorData {
+                    kind: sensors::SensorKind::Accelerometer,
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.acceleration)),
+                }],
+            },
+            sensors::SensorTable {
+                name: sensors::SensorKind::Light.as_str(),
+                datetime: std::mem::take(&mut self.lux_time),
+                data: vec![sensors::SensorData {
+                    kind: sensors::SensorKind::Light,
+                    data: sensors::SensorDataDyn::U16(std::mem::take(&mut self.lux)),
+                }],
+            },
+            sensors::SensorTable {
+                name: sensors::SensorKind::Capacitive.as_str(),
+                datetime: std::mem::take(&mut self.capsense_time),
+                data: vec![sensors::SensorData {
+                    kind: sensors::SensorKind::Capacitive,
+                    data: sensors::SensorDataDyn::Bool(std::mem::take(&mut self.capsense)),
+                }],
+            },
+            sensors::SensorTable {
+                name: sensors::SensorKind::BatteryVoltage.as_str(),
+                datetime: std::mem::take(&mut self.battery_voltage_time),
+                data: vec![sensors::SensorData {
+                    kind: sensors::SensorKind::BatteryVoltage,
+                    data: sensors::SensorDataDyn::U16(std::mem::take(&mut self.battery_voltage)),
+                }],
+            },
+        ];
+        // The IMU, epoch and heart rate tables only exist for files with such records.
+        let imu = self.imu.present().then(|| self.imu.take_table());
+        let epochs = self.epochs.present().then(|| self.epochs.take_table());
+        let heart_rate = self
+            .heart_rate
+            .present()
+            .then(|| self.heart_rate.take_table());
+        sensor_table_callback(
+            tables
+                .into_iter()
+                .chain(imu)
+                .chain(epochs)
+                .chain(heart_rate)
+                .filter_map(|table| table.select(selected))
+                .collect(),
+        );
+    }
+}
+
+/// Fill `buf` from `reader` as far as possible, returning the number of
+/// bytes read
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
 }
 
 pub struct LogRecordHeader {
@@ -128,9 +287,22 @@ impl std::fmt::Debug for LogRecordHeader {
 /// Error type for log record iteration
 #[derive(Debug)]
 pub enum LogRecordError {
-    InvalidSeparator { got: u8, offset: u64 },
-    RecordTooLarge { size: u16, max: usize },
-    UnexpectedEof { context: &'static str },
+    InvalidSeparator {
+        got: u8,
+        offset: u64,
+    },
+    RecordTooLarge {
+        size: u16,
+        max: usize,
+    },
+    UnexpectedEof {
+        context: &'static str,
+    },
+    /// The record was read completely, so parsing can continue after it
+    ChecksumMismatch {
+        expected: u8,
+        got: u8,
+    },
 }
 
 impl std::fmt::Display for LogRecordError {
@@ -149,14 +321,37 @@ impl std::fmt::Display for LogRecordError {
             LogRecordError::UnexpectedEof { context } => {
                 write!(f, "unexpected end of file while reading {}", context)
             }
+            LogRecordError::ChecksumMismatch { expected, got } => {
+                write!(
+                    f,
+                    "record checksum mismatch: expected 0x{:02x}, got 0x{:02x}",
+                    expected, got
+                )
+            }
         }
     }
 }
 
+/// Bytes read at a time while looking for the next valid record
+const RESYNC_READ_SIZE: usize = 4096;
+
+/// XOR checksum of a record: the one's complement of all header and payload bytes
+fn record_checksum(header: &[u8], payload: &[u8]) -> u8 {
+    !header.iter().chain(payload).fold(0u8, |acc, b| acc ^ b)
+}
+
 struct LogRecordIterator<R: Read> {
     reader: R,
     byte_offset: u64,
     record_index: usize,
+    /// Bytes read ahead while resynchronising, read again before `reader`
+    pushback: VecDeque<u8>,
+    /// Header of the record that failed to parse, the starting point of `resync`
+    failed_header: Option<[u8; 8]>,
+    /// Timestamp of the last record returned
+    last_timestamp: u32,
+    /// Byte offset of the current record
+    record_offset: u64,
 }
 
 impl<R: Read> LogRecordIterator<R> {
@@ -165,20 +360,49 @@ impl<R: Read> LogRecordIterator<R> {
             reader,
             byte_offset: 0,
             record_index: 0,
+            pushback: VecDeque::new(),
+            failed_header: None,
+            last_timestamp: 0,
+            record_offset: 0,
+        }
+    }
+
+    /// Fill `buf` as far as possible, returning the number of bytes read
+    fn fill(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let from_pushback = self.pushback.len().min(buf.len());
+        for (dst, src) in buf.iter_mut().zip(self.pushback.drain(..from_pushback)) {
+            *dst = src;
+        }
+        let filled = from_pushback + read_full(&mut self.reader, &mut buf[from_pushback..])?;
+        self.byte_offset += filled as u64;
+        Ok(filled)
+    }
+
+    /// Read from the underlying reader until `window` holds `len` bytes.
+    /// Returns `false` if the log ends before.
+    fn read_into(&mut self, window: &mut Vec<u8>, len: usize) -> bool {
+        let filled = window.len();
+        if filled >= len {
+            return true;
         }
+        window.resize(len, 0);
+        let read = read_full(&mut self.reader, &mut window[filled..]).unwrap_or(0);
+        window.truncate(filled + read);
+        window.len() == len
     }
 
     fn next<'a>(
         &mut self,
         data: &'a mut [u8],
     ) -> Option<std::result::Result<(LogRecordHeader, &'a [u8]), LogRecordError>> {
+        self.record_offset = self.byte_offset;
         let mut header = [0u8; 8];
-        match self.reader.read_exact(&mut header) {
-            Ok(_) => {
+        match self.fill(&mut header) {
+            Ok(8) => {
                 let record_header = LogRecordHeader::from_bytes(&header);
-                self.byte_offset += 8;
 
                 if !record_header.valid_separator() {
+                    self.failed_header = Some(header);
                     return Some(Err(LogRecordError::InvalidSeparator {
                         got: record_header.separator,
                         offset: self.byte_offset - 8,
@@ -187,6 +411,7 @@ impl<R: Read> LogRecordIterator<R> {
 
                 let record_size = record_header.record_size as usize + 1;
                 if record_size > data.len() {
+                    self.failed_header = Some(header);
                     return Some(Err(LogRecordError::RecordTooLarge {
                         size: record_header.record_size,
                         max: data.len(),
@@ -195,24 +420,99 @@ impl<R: Read> LogRecordIterator<R> {
 
                 let data = &mut data[0..record_size];
 
-                match self.reader.read_exact(data) {
-                    Ok(_) => {
-                        self.byte_offset += record_size as u64;
+                match self.fill(data) {
+                    Ok(n) if n == record_size => {
                         self.record_index += 1;
+                        let (payload, checksum) = data.split_at(record_size - 1);
+                        let expected = record_checksum(&header, payload);
+                        if checksum[0] != expected {
+                            return Some(Err(LogRecordError::ChecksumMismatch {
+                                expected,
+                                got: checksum[0],
+                            }));
+                        }
+                        self.last_timestamp = record_header.timestamp;
                         Some(Ok((record_header, data)))
                     }
-                    Err(_) => Some(Err(LogRecordError::UnexpectedEof {
+                    _ => Some(Err(LogRecordError::UnexpectedEof {
                         context: "record data",
                     })),
                 }
             }
-            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => None, // Normal EOF
+            Ok(_) => None, // Normal EOF
             Err(_) => Some(Err(LogRecordError::UnexpectedEof {
                 context: "record header",
             })),
         }
     }
 
+    /// After an invalid record, skip ahead to the next record that has a
+    /// known type, fits in `max_record_size`, has a matching checksum and
+    /// does not go back in time. Returns the skipped byte range `[from, to)`
+    /// (up to the end of the log if no valid record follows), or `None` if
+    /// there is no failed record to skip.
+    ///
+    /// The bytes are scanned in one window that is only read ahead as far as
+    /// a candidate needs, so every byte is read once. The payload is only
+    /// read for candidates whose header fields are plausible.
+    fn resync(&mut self, max_record_size: usize) -> Option<(u64, u64)> {
+        let failed_header = self.failed_header.take()?;
+        let from = self.byte_offset - failed_header.len() as u64;
+
+        // Bytes after the failed separator, `window[0]` is at `offset`
+        let mut window = failed_header[1..].to_vec();
+        window.extend(self.pushback.drain(..));
+        let mut offset = from + 1;
+        let mut pos = 0;
+        loop {
+            match window[pos..].iter().position(|&byte| byte == 0x1E) {
+                Some(i) => pos += i,
+                None => {
+                    offset += window.len() as u64;
+                    window.clear();
+                    pos = 0;
+                    if self.read_into(&mut window, RESYNC_READ_SIZE) || !window.is_empty() {
+                        continue;
+                    }
+                    break;
+                }
+            }
+            if !self.read_into(&mut window, pos + 8) {
+                break;
+            }
+
+            let header = LogRecordHeader::from_bytes(&window[pos..pos + 8]);
+            let record_size = header.record_size as usize + 1;
+            if !matches!(
+                LogRecordType::from_u8(header.record_type),
+                LogRecordType::Unknown
+            ) && record_size <= max_record_size
+                && header.timestamp >= self.last_timestamp
+                && self.read_into(&mut window, pos + 8 + record_size)
+            {
+                let record = &window[pos..pos + 8 + record_size];
+                let (header_bytes, payload) = record.split_at(8);
+                let (payload, checksum) = payload.split_at(record_size - 1);
+                if record_checksum(header_bytes, payload) == checksum[0] {
+                    let start = offset + pos as u64;
+                    self.pushback.extend(&window[pos..]);
+                    self.byte_offset = start;
+                    return Some((from, start));
+                }
+            }
+
+            pos += 1;
+            // Drop the scanned bytes once in a while
+            if pos >= RESYNC_READ_SIZE {
+                window.drain(..pos);
+                offset += pos as u64;
+                pos = 0;
+            }
+        }
+        self.byte_offset = offset + window.len() as u64;
+        Some((from, self.byte_offset))
+    }
+
     fn location(&self) -> FileLocation {
         FileLocation {
             byte_offset: Some(self.byte_offset),
@@ -221,17 +521,117 @@ impl<R: Read> LogRecordIterator<R> {
             sample_index: None,
         }
     }
+
+    /// Location of the record read last, for errors about a complete record
+    fn record_location(&self) -> FileLocation {
+        FileLocation {
+            byte_offset: Some(self.record_offset),
+            line_number: None,
+            record_index: Some(self.record_index - 1),
+            sample_index: None,
+        }
+    }
 }
 
+/// Reader for ActiGraph `.gt3x` files, both `log.bin` based and legacy
 #[derive(Default)]
 pub struct ActigraphReader {
     data: AccelerometerData,
+    options: sensors::ReadOptions,
 }
 
 impl ActigraphReader {
+    /// Reader with the default [`ReadOptions`](sensors::ReadOptions)
     pub fn new() -> ActigraphReader {
         Self::default()
     }
+
+    /// Reader that applies `options`, such as the time window, sensors or
+    /// idle sleep mode
+    pub fn with_options(options: sensors::ReadOptions) -> ActigraphReader {
+        ActigraphReader {
+            options,
+            ..Self::default()
+        }
+    }
+}
+
+/// The last Activity record before the time window. It is only unpacked
+/// when an idle sleep gap reaching into the window repeats its last sample.
+#[derive(Default)]
+struct PreWindowActivity {
+    pending: bool,
+    activity2: bool,
+    accel_scale: f32,
+    payload: Vec<u8>,
+}
+
+impl PreWindowActivity {
+    /// Keep `payload` in place of the previous record, unless it holds no samples
+    fn keep(&mut self, activity2: bool, accel_scale: f32, payload: &[u8]) {
+        let samples = if activity2 {
+            payload.len() / 6
+        } else {
+            payload.len() * 8 / 36
+        };
+        if samples == 0 {
+            return;
+        }
+        self.pending = true;
+        self.activity2 = activity2;
+        self.accel_scale = accel_scale;
+        self.payload.clear();
+        self.payload.extend_from_slice(payload);
+    }
+
+    /// Forget the kept record, once a later record has been decoded
+    fn discard(&mut self) {
+        self.pending = false;
+    }
+
+    /// Replace `last_sample` with the last sample of the kept record
+    fn resolve(&mut self, last_sample: &mut Option<[f32; 3]>) {
+        if !std::mem::take(&mut self.pending) {
+            return;
+        }
+        let sample = if self.activity2 {
+            activity2_samples(&self.payload).last()
+        } else {
+            activity_samples(&self.payload).last()
+        };
+        if let Some(sample) = sample {
+            *last_sample = Some(sample.map(|v| v as f32 / self.accel_scale));
+        }
+    }
+}
+
+/// Reconstruct the idle sleep span `[from, to)` according to `options.idle_sleep`
+fn fill_idle_sleep(
+    data: &mut AccelerometerData,
+    options: &sensors::ReadOptions,
+    result: &mut sensors::ReadResult,
+    (from, to): (i64, i64),
+    sample_rate: u32,
+    last_sample: Option<[f32; 3]>,
+) {
+    let sample = match options.idle_sleep {
+        sensors::IdleSleep::Leave => return,
+        // Nothing to repeat before the first sample
+        sensors::IdleSleep::FillLast => match last_sample {
+            Some(sample) => sample,
+            None => return,
+        },
+        sensors::IdleSleep::Nan => [f32::NAN; 3],
+    };
+    if !options.overlaps_window(from, to) {
+        return;
+    }
+
+    data.fill_idle(options, from, to, sample_rate, sample);
+    result.push_filled_span(
+        options.start.map_or(from, |start| from.max(start)),
+        options.end.map_or(to, |end| to.min(end)),
+    );
 }
 
 fn parse_metadata(record_data: &[u8]) -> Option<&str> {
@@ -241,7 +641,11 @@ fn parse_metadata(record_data: &[u8]) -> Option<&str> {
 struct Parameters {
     sample_rate: u32,
     accel_scale: f32,
+    imu_scales: imu::ImuScales,
     device_features: DeviceFeatures,
+    /// All parameters in record order, keyed by name (or `address:identifier`
+    /// if unknown)
+    values: Vec<(String, MetadataValue)>,
 }
 
 impl Default for Parameters {
@@ -249,7 +653,9 @@ impl Default for Parameters {
         Self {
             sample_rate: 30,
             accel_scale: 1.0,
+            imu_scales: imu::ImuScales::default(),
             device_features: DeviceFeatures::default(),
+            values: Vec::new(),
         }
     }
 }
@@ -280,6 +686,14 @@ fn parse_parameters(record_data: &[u8]) -> Parameters {
             record_data[offset + 7],
         ];
 
+        let key = match parameter_type {
+            ParameterType::Unknown => format!("{}:{}", param_address_space, param_identifier),
+            _ => parameter_type.to_string(),
+        };
+        params
+            .values
+            .push((key, parameter_value(&parameter_type, value_bytes)));
+
         match parameter_type {
             ParameterType::SampleRate => {
                 params.sample_rate = u32::from_le_bytes(value_bytes);
@@ -288,6 +702,22 @@ fn parse_parameters(record_data: &[u8]) -> Parameters {
                 let ssp_val = u32::from_le_bytes(value_bytes);
                 params.accel_scale = ssp_codec::decode(ssp_val) as f32;
             }
+            ParameterType::IMUGyroScale => {
+                let ssp_val = u32::from_le_bytes(value_bytes);
+                params.imu_scales.gyroscope = Some(ssp_codec::decode(ssp_val));
+            }
+            ParameterType::IMUMagScale => {
+                let ssp_val = u32::from_le_bytes(value_bytes);
+                params.imu_scales.magnetometer = Some(ssp_codec::decode(ssp_val));
+            }
+            ParameterType::IMUTempScale => {
+                let ssp_val = u32::from_le_bytes(value_bytes);
+                params.imu_scales.temperature = Some(ssp_codec::decode(ssp_val));
+            }
+            ParameterType::IMUTempOffset => {
+                let ssp_val = u32::from_le_bytes(value_bytes);
+                params.imu_scales.temperature_offset = Some(ssp_codec::decode(ssp_val));
+            }
             ParameterType::FeatureEnable => {
                 let x = u32::from_le_bytes(value_bytes);
                 params.device_features = DeviceFeatures {
@@ -305,6 +735,85 @@ fn parse_parameters(record_data: &[u8]) -> Parameters {
     params
 }
 
+/// Type a parameter value: SSP floats for scales and voltages, timestamps as
+/// Unix seconds, versions as `major.minor.build` and integers for everything
+/// else (including the calibration offsets, which are raw ADC counts)
+fn parameter_value(parameter_type: &ParameterType, value_bytes: [u8; 4]) -> MetadataValue {
+    let raw = u32::from_le_bytes(value_bytes);
+    match parameter_type {
+        ParameterType::FirmwareVersion | ParameterType::WirelessFirmwareVersion => {
+            MetadataValue::String(format!(
+                "{}.{}.{}",
+                raw >> 24,
+                (raw >> 16) & 0xFF,
+                raw & 0xFFFF
+            ))
+        }
+        ParameterType::BatteryVoltage
+        | ParameterType::IMUAccelScale
+        | ParameterType::IMUGyroScale
+        | ParameterType::IMUMagScale
+        | ParameterType::AccelScale
+        | ParameterType::IMUTempScale
+        | ParameterType::IMUTempOffset => MetadataValue::Float(ssp_codec::decode(raw)),
+        ParameterType::CalibrationTime
+        | ParameterType::TargetStartTime
+        | ParameterType::TargetStopTime
+        | ParameterType::TimeOfDay
+            if raw != 0 =>
+        {
+            DateTime::from_timestamp(raw as i64, 0)
+                .map_or(MetadataValue::Int(raw as i64), MetadataValue::DateTime)
+        }
+        ParameterType::UTCOffset
+        | ParameterType::NegativeGOffsetX
+        | ParameterType::NegativeGOffsetY
+        | ParameterType::NegativeGOffsetZ
+        | ParameterType::PositiveGOffsetX
+        | ParameterType::PositiveGOffsetY
+        | ParameterType::PositiveGOffsetZ
+        | ParameterType::ZeroGOffsetX
+        | ParameterType::ZeroGOffsetY
+        | ParameterType::ZeroGOffsetZ
+        | ParameterType::IMUNegativeGOffsetX
+        | ParameterType::IMUNegativeGOffsetY
+        | ParameterType::IMUNegativeGOffsetZ
+        | ParameterType::IMUPositiveGOffsetX
+        | ParameterType::IMUPositiveGOffsetY
+        | ParameterType::IMUPositiveGOffsetZ
+        | ParameterType::IMUZeroGOffsetX
+        | ParameterType::IMUZeroGOffsetY
+        | ParameterType::IMUZeroGOffsetZ => MetadataValue::Int(raw as i32 as i64),
+        // Identifiers, not quantities
+        ParameterType::WirelessSerialNumber
+        | ParameterType::HRMSerialNumberH
+        | ParameterType::HRMSerialNumberL => MetadataValue::String(raw.to_string()),
+        _ => MetadataValue::Int(raw as i64),
+    }
+}
+
+/// Samples of an `Activity` record: packed 12-bit Y, X, Z triplets
+fn activity_samples(payload: &[u8]) -> impl Iterator<Item = [i16; 3]> + '_ {
+    let mut reader = BitReader::new(payload);
+    std::iter::from_fn(move || {
+        let y = reader.read_i16(12).ok()?;
+        let x = reader.read_i16(12).ok()?;
+        let z = reader.read_i16(12).ok()?;
+        Some([x, y, z])
+    })
+}
+
+/// Samples of an `Activity2` record: little-endian 16-bit X, Y, Z triplets
+fn activity2_samples(payload: &[u8]) -> impl Iterator<Item = [i16; 3]> + '_ {
+    payload.chunks_exact(6).map(|sample| {
+        [
+            i16::from_le_bytes([sample[0], sample[1]]),
+            i16::from_le_bytes([sample[2], sample[3]]),
+            i16::from_le_bytes([sample[4], sample[5]]),
+        ]
+    })
+}
+
 fn parse_lux(data: &[u8]) -> u16 {
     u16::from_le_bytes([data[0], data[1]])
 }
@@ -317,9 +826,155 @@ fn parse_capsense(data: &[u8]) -> bool {
     data.get(4).is_some_and(|&b| b != 0)
 }
 
-impl<'a> sensors::SensorsFormatReader<'a> for ActigraphReader {
+fn hex_string(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn open_archive<R: Read + std::io::Seek>(reader: R) -> Result<zip::ZipArchive<R>> {
+    zip::ZipArchive::new(reader).map_err(|e| ActfastError::Parse {
+        format: FileFormat::ActigraphGt3x,
+        message: format!("failed to open ZIP archive: {}", e),
+        location: FileLocation::new(),
+    })
+}
+
+fn archive_file<'a, R: Read + std::io::Seek>(
+    archive: &'a mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<zip::read::ZipFile<'a, R>> {
+    archive.by_name(name).map_err(|_| ActfastError::Parse {
+        format: FileFormat::ActigraphGt3x,
+        message: format!("missing required file '{}' in archive", name),
+        location: FileLocation::new(),
+    })
+}
+
+/// Header values from `info.txt`, dates are .NET ticks
+struct InfoTxt {
+    sample_rate: usize,
+    date_start: usize,
+    date_end: usize,
+    accel_scale: Option<f32>,
+}
+
+/// Read `info.txt`, emitting every entry as metadata
+fn read_info_txt<R: Read + std::io::Seek, M: FnMut(sensors::MetadataEntry)>(
+    archive: &mut zip::ZipArchive<R>,
+    mut metadata_callback: M,
+) -> Result<InfoTxt> {
+    let mut info = InfoTxt {
+        sample_rate: 30,
+        date_start: 0,
+        date_end: 0,
+        accel_scale: None,
+    };
+
+    let info_file = archive_file(archive, GT3X_FILE_INFO)?;
+
+    for (line_num, line) in BufReader::new(info_file).lines().enumerate() {
+        let line = line.map_err(|e| ActfastError::Io {
+            source: e,
+            context: format!("reading {} line {}", GT3X_FILE_INFO, line_num + 1),
+        })?;
+
+        let parts: Vec<&str> = line.splitn(2, ": ").collect();
+        if parts.len() == 2 {
+            metadata_callback(sensors::MetadataEntry {
+                category: "info",
+                key: parts[0],
+                value: info_txt_value(parts[0], parts[1]),
+            });
+
+            match parts[0] {
+                "Sample Rate" => {
+                    info.sample_rate = parts[1].parse().unwrap_or(30);
+                }
+                "Start Date" => {
+                    info.date_start = parts[1].parse().unwrap_or(0);
+                }
+                "Last Sample Time" => {
+                    info.date_end = parts[1].parse().unwrap_or(0);
+                }
+                "Acceleration Scale" => {
+                    info.accel_scale = parts[1].trim().parse().ok();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(info)
+}
+
+/// Type an `info.txt` value by its key
+fn info_txt_value<'a>(key: &str, value: &'a str) -> MetadataValue<&'a str> {
+    match key {
+        "Start Date" | "Stop Date" | "Last Sample Time" | "Download Date" => value
+            .trim()
+            .parse()
+            .ok()
+            .and_then(ticks_to_datetime)
+            .map_or(MetadataValue::String(value), MetadataValue::DateTime),
+        "Sample Rate" | "Board Revision" | "Unexpected Resets" => MetadataValue::int_or_str(value),
+        "Battery Voltage" | "Acceleration Scale" | "Acceleration Min" | "Acceleration Max" => {
+            MetadataValue::float_or_str(value)
+        }
+        _ => MetadataValue::String(value),
+    }
+}
+
+const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+
+/// Convert .NET ticks (100 ns intervals since 0001-01-01) to Unix nanoseconds
+fn ticks_to_nanos(ticks: usize) -> Option<i64> {
+    (ticks > 0).then(|| (ticks as i64 - UNIX_EPOCH_TICKS) * 100)
+}
+
+/// Convert .NET ticks to a datetime, which unlike nanoseconds covers year 1
+fn ticks_to_datetime(ticks: i64) -> Option<DateTime<Utc>> {
+    let ticks = ticks - UNIX_EPOCH_TICKS;
+    DateTime::from_timestamp(
+        ticks.div_euclid(10_000_000),
+        (ticks.rem_euclid(10_000_000) * 100) as u32,
+    )
+}
+
+fn emit_device_features<M: FnMut(sensors::MetadataEntry)>(
+    features: &DeviceFeatures,
+    mut metadata_callback: M,
+) {
+    for (key, enabled) in [
+        ("data_summary", features.data_summary),
+        ("epoch_data", features.epoch_data),
+        ("heart_rate_monitor", features.heart_rate_monitor),
+        ("no_raw_data", features.no_raw_data),
+        ("proximity_tagging", features.proximity_tagging),
+        ("sleep_mode", features.sleep_mode),
+    ] {
+        metadata_callback(sensors::MetadataEntry {
+            category: "device_feature_enabled",
+            key,
+            value: MetadataValue::Bool(enabled),
+        });
+    }
+}
+
+fn emit_parameters<M: FnMut(sensors::MetadataEntry)>(
+    parameters: &Parameters,
+    mut metadata_callback: M,
+) {
+    for (key, value) in &parameters.values {
+        metadata_callback(sensors::MetadataEntry {
+            category: "parameters",
+            key,
+            value: value.as_deref(),
+        });
+    }
+}
+
+impl sensors::SensorsFormatReader for ActigraphReader {
     fn read<R: std::io::Read + std::io::Seek, M, S>(
-        &'a mut self,
+        &mut self,
         reader: R,
         mut metadata_callback: M,
         mut sensor_table_callback: S,
@@ -327,70 +982,35 @@ impl<'a> sensors::SensorsFormatReader<'a> for ActigraphReader {
     ) -> Result<sensors::ReadResult>
     where
         M: FnMut(sensors::MetadataEntry),
-        S: FnMut(sensors::SensorTable<'a>),
+        S: FnMut(Vec<sensors::SensorTable>),
     {
         let mut result = sensors::ReadResult::new();
 
-        let mut archive = zip::ZipArchive::new(reader).map_err(|e| ActfastError::Parse {
-            format: FileFormat::ActigraphGt3x,
-            message: format!("failed to open ZIP archive: {}", e),
-            location: FileLocation::new(),
-        })?;
+        let mut archive = open_archive(reader)?;
+        let header = read_info_txt(&mut archive, &mut metadata_callback)?;
 
-        // Read metadata from info.txt
-        let mut header_sample_rate: usize = 30;
-        let mut header_date_start: usize = 0;
-        let mut header_date_end: usize = 0;
-
-        let info_file = archive
-            .by_name(GT3X_FILE_INFO)
-            .map_err(|_| ActfastError::Parse {
-                format: FileFormat::ActigraphGt3x,
-                message: format!("missing required file '{}' in archive", GT3X_FILE_INFO),
-                location: FileLocation::new(),
-            })?;
-
-        for (line_num, line) in BufReader::new(info_file).lines().enumerate() {
-            let line = line.map_err(|e| ActfastError::Io {
-                source: e,
-                context: format!("reading {} line {}", GT3X_FILE_INFO, line_num + 1),
-            })?;
-
-            let parts: Vec<&str> = line.splitn(2, ": ").collect();
-            if parts.len() == 2 {
-                metadata_callback(sensors::MetadataEntry {
-                    category: "info",
-                    key: parts[0],
-                    value: parts[1],
-                });
-
-                match parts[0] {
-                    "Sample Rate" => {
-                        header_sample_rate = parts[1].parse().unwrap_or(30);
-                    }
-                    "Start Date" => {
-                        header_date_start = parts[1].parse().unwrap_or(0);
-                    }
-                    "Last Sample Time" => {
-                        header_date_end = parts[1].parse().unwrap_or(0);
-                    }
-                    _ => {}
-                }
-            }
+        // Estimate & reserve data sizes (a single chunk when streaming)
+        if let Some(chunk_samples) = self.options.chunk_samples {
+            self.data
+                .reserve(chunk_samples, chunk_samples / header.sample_rate.max(1));
+        } else {
+            self.data
+                .reserve_estimate(header.sample_rate, header.date_start, header.date_end);
         }
 
-        // Estimate & reserve data sizes
-        self.data
-            .reserve_estimate(header_sample_rate, header_date_start, header_date_end);
+        if legacy::is_legacy(&archive) {
+            self.read_legacy(
+                &mut archive,
+                &header,
+                &mut sensor_table_callback,
+                &mut result,
+                lenient,
+            )?;
+            return Ok(result);
+        }
 
         // Read log data
-        let log_file = archive
-            .by_name(GT3X_FILE_LOG)
-            .map_err(|_| ActfastError::Parse {
-                format: FileFormat::ActigraphGt3x,
-                message: format!("missing required file '{}' in archive", GT3X_FILE_LOG),
-                location: FileLocation::new(),
-            })?;
+        let log_file = archive_file(&mut archive, GT3X_FILE_LOG)?;
 
         let mut log = BufReader::new(log_file);
         let mut sample_rate = 30u32;
@@ -398,33 +1018,134 @@ impl<'a> sensors::SensorsFormatReader<'a> for ActigraphReader {
         let mut record_data = [0u8; u16::MAX as usize + 1];
         let mut it = LogRecordIterator::new(&mut log);
         let mut metadata_counter = 0;
+        let selected = self.options.sensors;
+        // For idle sleep reconstruction
+        let fill_idle = self.options.idle_sleep != sensors::IdleSleep::Leave
+            && selected.contains(sensors::SensorKind::Accelerometer);
+        let mut last_activity_time: Option<i64> = None;
+        // Set when a record is skipped, so the next gap between activity
+        // records may be lost data rather than idle sleep.
+        let mut records_lost = false;
+        let mut last_sample: Option<[f32; 3]> = None;
+        let mut pre_window = PreWindowActivity::default();
+        // IMU schemas by id and the scales to apply to their samples
+        let mut schemas: HashMap<u16, imu::SensorSchema> = HashMap::new();
+        let mut imu_scales = imu::ImuScales::default();
+        let imu_selected = [
+            sensors::SensorKind::Gyroscope,
+            sensors::SensorKind::Magnetometer,
+            sensors::SensorKind::Temperature,
+        ]
+        .into_iter()
+        .any(|kind| selected.contains(kind));
+        let epochs_selected = selected.contains_any(&[
+            sensors::SensorKind::ActivityCounts,
+            sensors::SensorKind::Steps,
+            sensors::SensorKind::Inclinometer,
+        ]);
+        let heart_rate_selected = selected.contains_any(&[
+            sensors::SensorKind::HeartRate,
+            sensors::SensorKind::RrInterval,
+        ]);
+        let mut ant_decoder = heart_rate::AntDecoder::default();
 
         while let Some(record_result) = it.next(&mut record_data) {
             let (record_header, record_data) = match record_result {
                 Ok(data) => data,
+                Err(e @ LogRecordError::ChecksumMismatch { .. }) => {
+                    result.checksum_mismatches += 1;
+                    let error = ActfastError::Parse {
+                        format: FileFormat::ActigraphGt3x,
+                        message: e.to_string(),
+                        location: it.record_location(),
+                    };
+                    if !lenient {
+                        return Err(error);
+                    }
+                    // Skip the record, the next one follows directly.
+                    result.warnings.push(error.to_string());
+                    records_lost = true;
+                    continue;
+                }
                 Err(e) => {
                     let error = ActfastError::Parse {
                         format: FileFormat::ActigraphGt3x,
                         message: e.to_string(),
                         location: it.location(),
                     };
-                    if lenient {
-                        result.warnings.push(error.to_string());
-                        break; // Stop processing but return what we have
-                    } else {
+                    if !lenient {
                         return Err(error);
                     }
+                    // Skip to the next valid record, or stop processing
+                    // but return what we have.
+                    match it.resync(u16::MAX as usize + 1) {
+                        Some((from, to)) => {
+                            result.warnings.push(format!(
+                                "{} (skipped bytes {}..{} to the next valid record)",
+                                error, from, to
+                            ));
+                            records_lost = true;
+                            continue;
+                        }
+                        None => {
+                            result.warnings.push(error.to_string());
+                            break;
+                        }
+                    }
                 }
             };
 
-            match LogRecordType::from_u8(record_header.record_type) {
+            let record_type = LogRecordType::from_u8(record_header.record_type);
+            let record_time = record_header.datetime_nanos();
+            let is_activity = matches!(
+                record_type,
+                LogRecordType::Activity | LogRecordType::Activity2
+            );
+
+            // Seconds without an activity record were spent in idle sleep,
+            // unless records were skipped in between.
+            if fill_idle && is_activity {
+                if let Some(previous) = last_activity_time
+                    && !records_lost
+                    && record_time > previous + NANOS_PER_SECOND
+                {
+                    if self
+                        .options
+                        .overlaps_window(previous + NANOS_PER_SECOND, record_time)
+                    {
+                        pre_window.resolve(&mut last_sample);
+                    }
+                    fill_idle_sleep(
+                        &mut self.data,
+                        &self.options,
+                        &mut result,
+                        (previous + NANOS_PER_SECOND, record_time),
+                        sample_rate,
+                        last_sample,
+                    );
+                }
+                last_activity_time = Some(record_time);
+                records_lost = false;
+            }
+
+            // Records are time-ordered, nothing after the window is needed.
+            if self.options.after_window(record_time)
+                && !matches!(
+                    record_type,
+                    LogRecordType::Metadata | LogRecordType::Parameters
+                )
+            {
+                break;
+            }
+
+            match record_type {
                 LogRecordType::Metadata => {
                     if let Some(metadata) = parse_metadata(record_data) {
                         metadata_counter += 1;
                         metadata_callback(sensors::MetadataEntry {
                             category: "metadata",
                             key: &format!("metadata_{}", metadata_counter),
-                            value: metadata,
+                            value: MetadataValue::String(metadata),
                         });
                     }
                 }
@@ -432,126 +1153,334 @@ impl<'a> sensors::SensorsFormatReader<'a> for ActigraphReader {
                     let params = parse_parameters(record_data);
                     sample_rate = params.sample_rate;
                     accel_scale = params.accel_scale;
+                    imu_scales = params.imu_scales;
 
-                    metadata_callback(sensors::MetadataEntry {
-                        category: "device_feature_enabled",
-                        key: "data_summary",
-                        value: &format!("{}", params.device_features.data_summary),
-                    });
-                    metadata_callback(sensors::MetadataEntry {
-                        category: "device_feature_enabled",
-                        key: "epoch_data",
-                        value: &format!("{}", params.device_features.epoch_data),
-                    });
-                    metadata_callback(sensors::MetadataEntry {
-                        category: "device_feature_enabled",
-                        key: "heart_rate_monitor",
-                        value: &format!("{}", params.device_features.heart_rate_monitor),
-                    });
-                    metadata_callback(sensors::MetadataEntry {
-                        category: "device_feature_enabled",
-                        key: "no_raw_data",
-                        value: &format!("{}", params.device_features.no_raw_data),
-                    });
-                    metadata_callback(sensors::MetadataEntry {
-                        category: "device_feature_enabled",
-                        key: "proximity_tagging",
-                        value: &format!("{}", params.device_features.proximity_tagging),
-                    });
-                    metadata_callback(sensors::MetadataEntry {
-                        category: "device_feature_enabled",
-                        key: "sleep_mode",
-                        value: &format!("{}", params.device_features.sleep_mode),
-                    });
+                    emit_device_features(&params.device_features, &mut metadata_callback);
+                    emit_parameters(&params, &mut metadata_callback);
                 }
-                LogRecordType::Activity => {
+                LogRecordType::Activity | LogRecordType::Activity2
+                    if selected.contains(sensors::SensorKind::Accelerometer) =>
+                {
+                    // Each record holds one second of samples; skip records
+                    // outside the window without unpacking them. The last one
+                    // before the window is kept, in case an idle sleep gap
+                    // into the window repeats its last sample.
+                    let payload = &record_data[0..record_data.len() - 1];
+                    let activity2 = matches!(record_type, LogRecordType::Activity2);
+                    if !self
+                        .options
+                        .overlaps_window(record_time, record_time + NANOS_PER_SECOND)
+                    {
+                        if self.options.idle_sleep == sensors::IdleSleep::FillLast {
+                            pre_window.keep(activity2, accel_scale, payload);
+                        }
+                        continue;
+                    }
+
                     let dt = record_header.datetime();
-                    let mut reader = BitReader::new(&record_data[0..record_data.len() - 1]);
-                    let mut i = 0u32;
-
-                    while let Ok(y) = reader.read_i16(12) {
-                        let x = match reader.read_i16(12) {
-                            Ok(val) => val,
-                            Err(_) => break,
-                        };
-                        let z = match reader.read_i16(12) {
-                            Ok(val) => val,
-                            Err(_) => break,
-                        };
-
-                        let timestamp_nanos = datetime_add_hz(dt, sample_rate, i)
-                            .timestamp_nanos_opt()
-                            .unwrap_or_default();
-
-                        self.data.acceleration_time.push(timestamp_nanos);
-                        self.data.acceleration.extend(&[
-                            x as f32 / accel_scale,
-                            y as f32 / accel_scale,
-                            z as f32 / accel_scale,
-                        ]);
-
-                        i += 1;
+                    let record_last_sample = if !activity2 {
+                        let samples = activity_samples(payload);
+                        self.data.push_activity(
+                            &self.options,
+                            dt,
+                            sample_rate,
+                            accel_scale,
+                            samples,
+                        )
+                    } else {
+                        let samples = activity2_samples(payload);
+                        self.data.push_activity(
+                            &self.options,
+                            dt,
+                            sample_rate,
+                            accel_scale,
+                            samples,
+                        )
+                    };
+
+                    match record_last_sample {
+                        Some(sample) => {
+                            pre_window.discard();
+                            last_sample = Some(sample);
+                        }
+                        // An empty record is an idle second as well.
+                        None if fill_idle => {
+                            pre_window.resolve(&mut last_sample);
+                            fill_idle_sleep(
+                                &mut self.data,
+                                &self.options,
+                                &mut result,
+                                (record_time, record_time + NANOS_PER_SECOND),
+                                sample_rate,
+                                last_sample,
+                            );
+                        }
+                        None => {}
+                    }
+                }
+                LogRecordType::SensorSchema => {
+                    let payload = &record_data[0..record_data.len() - 1];
+                    if let Some(schema) = imu::SensorSchema::parse(payload) {
+                        self.data.imu.add_schema(&schema, selected);
+                        schemas.insert(schema.id, schema);
+                    }
+                }
+                LogRecordType::SensorData
+                    if imu_selected
+                        && self
+                            .options
+                            .overlaps_window(record_time, record_time + NANOS_PER_SECOND) =>
+                {
+                    let payload = &record_data[0..record_data.len() - 1];
+                    if payload.len() < 2 {
+                        continue;
+                    }
+                    let schema_id = u16::from_le_bytes([payload[0], payload[1]]);
+                    if let Some(schema) = schemas.get(&schema_id) {
+                        self.data.imu.push_samples(
+                            schema,
+                            &imu_scales,
+                            &self.options,
+                            record_time,
+                            &payload[2..],
+                        );
+                    }
+                }
+                LogRecordType::Epoch
+                | LogRecordType::Epoch2
+                | LogRecordType::Epoch3
+                | LogRecordType::Epoch4
+                    if epochs_selected && self.options.in_window(record_time) =>
+                {
+                    let payload = &record_data[0..record_data.len() - 1];
+                    if let Some(epoch) = epoch::EpochLayout::of(&record_type)
+                        .and_then(|layout| epoch::Epoch::parse(layout, payload))
+                    {
+                        self.data.epochs.push(record_time, &epoch);
+                    }
+                }
+                LogRecordType::HeartRateBPM
+                | LogRecordType::HeartRateAnt
+                | LogRecordType::HeartRateBle
+                    if heart_rate_selected =>
+                {
+                    let payload = &record_data[0..record_data.len() - 1];
+                    let heart_rate = match record_type {
+                        LogRecordType::HeartRateBPM => heart_rate::parse_bpm(payload),
+                        // Decoded outside the window too, for the intervals
+                        // to the previous beat
+                        LogRecordType::HeartRateAnt => ant_decoder.parse(payload),
+                        _ => heart_rate::parse_ble(payload),
+                    };
+                    if let Some(heart_rate) = heart_rate
+                        && self.options.in_window(record_time)
+                    {
+                        self.data.heart_rate.push(record_time, &heart_rate);
                     }
                 }
-                LogRecordType::Lux => {
+                LogRecordType::Lux
+                    if selected.contains(sensors::SensorKind::Light)
+                        && self.options.in_window(record_time) =>
+                {
                     let lux = parse_lux(record_data);
-                    let timestamp_nanos = record_header.datetime_nanos();
                     self.data.lux.push(lux);
-                    self.data.lux_time.push(timestamp_nanos);
+                    self.data.lux_time.push(record_time);
                 }
-                LogRecordType::Battery => {
+                LogRecordType::Battery
+                    if selected.contains(sensors::SensorKind::BatteryVoltage)
+                        && self.options.in_window(record_time) =>
+                {
                     let voltage = parse_battery_voltage(record_data);
-                    let timestamp_nanos = record_header.datetime_nanos();
                     self.data.battery_voltage.push(voltage);
-                    self.data.battery_voltage_time.push(timestamp_nanos);
+                    self.data.battery_voltage_time.push(record_time);
                 }
-                LogRecordType::Capsense => {
+                LogRecordType::Capsense
+                    if selected.contains(sensors::SensorKind::Capacitive)
+                        && self.options.in_window(record_time) =>
+                {
                     let state = parse_capsense(record_data);
-                    let timestamp_nanos = record_header.datetime_nanos();
                     self.data.capsense.push(state);
-                    self.data.capsense_time.push(timestamp_nanos);
+                    self.data.capsense_time.push(record_time);
+                }
+                LogRecordType::Event
+                | LogRecordType::Tag
+                | LogRecordType::FifoError
+                | LogRecordType::FifoDump
+                    if self.options.in_window(record_time) =>
+                {
+                    let payload = &record_data[0..record_data.len() - 1];
+                    let kind = match record_type {
+                        LogRecordType::Event => "event",
+                        LogRecordType::Tag => "tag",
+                        LogRecordType::FifoError => "fifo_error",
+                        _ => "fifo_dump",
+                    };
+                    if matches!(record_type, LogRecordType::FifoError) {
+                        result.warnings.push(format!(
+                            "device reported a FIFO error at {} (payload {})",
+                            record_header.datetime().to_rfc3339(),
+                            hex_string(payload),
+                        ));
+                    }
+                    result.events.push(sensors::Event {
+                        time: record_time,
+                        kind,
+                        payload: payload.to_vec(),
+                    });
                 }
                 _ => {}
             }
+
+            if self.options.chunk_full(self.data.buffered_len()) {
+                self.data
+                    .emit_sensor_tables(selected, &mut sensor_table_callback);
+                if self.options.cancelled() {
+                    return Ok(result);
+                }
+            }
         }
 
-        sensor_table_callback(sensors::SensorTable {
-            name: sensors::SensorKind::Accelerometer.as_str(),
-            datetime: &self.data.acceleration_time,
-            data: vec![sensors::SensorData {
-                kind: sensors::SensorKind::Accelerometer,
-                data: sensors::SensorDataDyn::F32(&self.data.acceleration),
-            }],
-        });
+        self.data
+            .emit_sensor_tables(selected, &mut sensor_table_callback);
 
-        sensor_table_callback(sensors::SensorTable {
-            name: sensors::SensorKind::Light.as_str(),
-            datetime: &self.data.lux_time,
-            data: vec![sensors::SensorData {
-                kind: sensors::SensorKind::Light,
-                data: sensors::SensorDataDyn::U16(&self.data.lux),
-            }],
-        });
+        Ok(result)
+    }
 
-        sensor_table_callback(sensors::SensorTable {
-            name: sensors::SensorKind::Capacitive.as_str(),
-            datetime: &self.data.capsense_time,
-            data: vec![sensors::SensorData {
-                kind: sensors::SensorKind::Capacitive,
-                data: sensors::SensorDataDyn::Bool(&self.data.capsense),
-            }],
-        });
+    fn info<R: std::io::Read + std::io::Seek, M>(
+        &mut self,
+        reader: R,
+        mut metadata_callback: M,
+    ) -> Result<sensors::RecordingInfo>
+    where
+        M: FnMut(sensors::MetadataEntry),
+    {
+        let mut archive = open_archive(reader)?;
+        let header = read_info_txt(&mut archive, &mut metadata_callback)?;
+
+        let mut info = sensors::RecordingInfo {
+            first_timestamp: ticks_to_nanos(header.date_start),
+            last_timestamp: ticks_to_nanos(header.date_end),
+            sample_rate: Some(header.sample_rate as f64),
+            ..Default::default()
+        };
+        let mut no_raw_data = false;
+        let mut epoch_data = false;
+        let mut heart_rate_monitor = false;
+        let mut imu_sensors = Vec::new();
+
+        if legacy::is_legacy(&archive) {
+            info.estimated_samples = info.estimate_samples();
+            info.sensors = vec![sensors::SensorKind::Accelerometer];
+            if archive.index_for_name(GT3X_FILE_LUX).is_some() {
+                info.sensors.push(sensors::SensorKind::Light);
+            }
+            return Ok(info);
+        }
 
-        sensor_table_callback(sensors::SensorTable {
-            name: sensors::SensorKind::BatteryVoltage.as_str(),
-            datetime: &self.data.battery_voltage_time,
-            data: vec![sensors::SensorData {
-                kind: sensors::SensorKind::BatteryVoltage,
-                data: sensors::SensorDataDyn::U16(&self.data.battery_voltage),
-            }],
-        });
+        // Metadata and parameters are logged before the samples. The light,
+        // capsense and battery records only show up among the samples, so the
+        // first minutes after the first activity (or epoch) record are read
+        // as well. Bad records are skipped as in lenient mode.
+        let log_file = archive_file(&mut archive, GT3X_FILE_LOG)?;
+        let mut log = BufReader::new(log_file);
+        let mut record_data = [0u8; u16::MAX as usize + 1];
+        let mut it = LogRecordIterator::new(&mut log);
+        let mut metadata_counter = 0;
+        let mut scan_until: Option<i64> = None;
+        let mut logged = sensors::SensorSet::EMPTY;
+        let logged_kinds = [
+            sensors::SensorKind::Light,
+            sensors::SensorKind::Capacitive,
+            sensors::SensorKind::BatteryVoltage,
+        ];
 
-        Ok(result)
+        while let Some(record_result) = it.next(&mut record_data) {
+            let (record_header, record_data) = match record_result {
+                Ok(data) => data,
+                Err(LogRecordError::ChecksumMismatch { .. }) => continue,
+                Err(_) if it.resync(u16::MAX as usize + 1).is_some() => continue,
+                Err(_) => break,
+            };
+            if let Some(until) = scan_until
+                && (record_header.datetime_nanos() >= until
+                    || logged_kinds.iter().all(|&kind| logged.contains(kind)))
+            {
+                break;
+            }
+
+            match LogRecordType::from_u8(record_header.record_type) {
+                LogRecordType::Metadata if scan_until.is_none() => {
+                    if let Some(metadata) = parse_metadata(record_data) {
+                        metadata_counter += 1;
+                        metadata_callback(sensors::MetadataEntry {
+                            category: "metadata",
+                            key: &format!("metadata_{}", metadata_counter),
+                            value: MetadataValue::String(metadata),
+                        });
+                    }
+                }
+                LogRecordType::Parameters if scan_until.is_none() => {
+                    let params = parse_parameters(record_data);
+                    info.sample_rate = Some(params.sample_rate as f64);
+                    no_raw_data = params.device_features.no_raw_data;
+                    epoch_data = params.device_features.epoch_data;
+                    heart_rate_monitor = params.device_features.heart_rate_monitor;
+                    emit_device_features(&params.device_features, &mut metadata_callback);
+                    emit_parameters(&params, &mut metadata_callback);
+                }
+                LogRecordType::SensorSchema => {
+                    let payload = &record_data[0..record_data.len() - 1];
+                    if let Some(schema) = imu::SensorSchema::parse(payload) {
+                        for kind in schema.sensors() {
+                            if !imu_sensors.contains(&kind) {
+                                imu_sensors.push(kind);
+                            }
+                        }
+                    }
+                }
+                LogRecordType::Activity | LogRecordType::Activity2 if scan_until.is_none() => {
+                    info.first_timestamp = Some(record_header.datetime_nanos());
+                    scan_until = Some(record_header.datetime_nanos() + INFO_SCAN_NANOS);
+                }
+                // Files recorded in epoch mode may have no activity records.
+                LogRecordType::Epoch
+                | LogRecordType::Epoch2
+                | LogRecordType::Epoch3
+                | LogRecordType::Epoch4
+                    if scan_until.is_none() =>
+                {
+                    info.first_timestamp = Some(record_header.datetime_nanos());
+                    scan_until = Some(record_header.datetime_nanos() + INFO_SCAN_NANOS);
+                    epoch_data = true;
+                }
+                LogRecordType::Lux => logged.insert(sensors::SensorKind::Light),
+                LogRecordType::Capsense => logged.insert(sensors::SensorKind::Capacitive),
+                LogRecordType::Battery => logged.insert(sensors::SensorKind::BatteryVoltage),
+                _ => {}
+            }
+        }
+
+        info.estimated_samples = info.estimate_samples();
+        if !no_raw_data {
+            info.sensors.push(sensors::SensorKind::Accelerometer);
+        }
+        info.sensors.extend(
+            logged_kinds
+                .into_iter()
+                .filter(|&kind| logged.contains(kind)),
+        );
+        info.sensors.extend(imu_sensors);
+        if epoch_data {
+            info.sensors.push(sensors::SensorKind::ActivityCounts);
+        }
+        if heart_rate_monitor {
+            info.sensors.extend([
+                sensors::SensorKind::HeartRate,
+                sensors::SensorKind::RrInterval,
+            ]);
+        }
+
+        Ok(info)
     }
 }
 
@@ -573,29 +1502,83 @@ mod tests {
             |entry| {
                 metadata.insert(
                     (entry.category.to_owned(), entry.key.to_owned()),
-                    entry.value.to_owned(),
+                    entry.value.into_owned(),
                 );
             },
-            |table| {
-                sensor_table.insert(table.name, table);
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
             },
             false,
         );
-        assert!(result.is_ok());
-        assert!(result.unwrap().warnings.is_empty());
+        let result = result.unwrap();
+        assert!(result.warnings.is_empty());
+        assert_eq!(
+            result.events,
+            [
+                sensors::Event {
+                    time: 1714488779 * NANOS_PER_SECOND,
+                    kind: "event",
+                    payload: vec![0x00],
+                },
+                sensors::Event {
+                    time: 1714488780 * NANOS_PER_SECOND,
+                    kind: "event",
+                    payload: vec![0x05, 0x01, 0x02],
+                },
+            ]
+        );
 
-        assert_eq!(metadata.len(), 25);
+        assert_eq!(metadata.len(), 65);
         assert_eq!(sensor_table.len(), 4);
 
-        assert_eq!(metadata[&("info".into(), "Sample Rate".into())], "60");
+        assert_eq!(
+            metadata[&("info".into(), "Sample Rate".into())],
+            MetadataValue::Int(60)
+        );
         assert_eq!(
             metadata[&("info".into(), "Start Date".into())],
-            "638500855800000000"
+            MetadataValue::DateTime(DateTime::from_timestamp(1714488780, 0).unwrap())
         );
         assert_eq!(
             metadata[&("info".into(), "Last Sample Time".into())],
-            "638500857000000000"
+            MetadataValue::DateTime(DateTime::from_timestamp(1714488900, 0).unwrap())
         );
+        assert_eq!(
+            metadata[&("info".into(), "Acceleration Scale".into())],
+            MetadataValue::Float(256.0)
+        );
+        assert_eq!(
+            metadata[&("info".into(), "Serial Number".into())],
+            MetadataValue::String("MOS2E17210537".into())
+        );
+        assert_eq!(
+            metadata[&("parameters".into(), "FirmwareVersion".into())],
+            MetadataValue::String("1.9.2".into())
+        );
+        assert_eq!(
+            metadata[&("parameters".into(), "CalibrationTime".into())],
+            MetadataValue::DateTime(DateTime::from_timestamp(1619533217, 0).unwrap())
+        );
+        assert_eq!(
+            metadata[&("parameters".into(), "NegativeGOffsetX".into())],
+            MetadataValue::Int(-166)
+        );
+        assert_eq!(
+            metadata[&("parameters".into(), "TargetStopTime".into())],
+            MetadataValue::Int(0)
+        );
+        assert_eq!(
+            metadata[&("parameters".into(), "0:37".into())],
+            MetadataValue::Int(1024)
+        );
+        let MetadataValue::Float(voltage) =
+            metadata[&("parameters".into(), "BatteryVoltage".into())]
+        else {
+            panic!("Expected a float");
+        };
+        assert_approx_eq!(voltage, 4.005, 1e-3);
 
         assert_eq!(sensor_table["acceleration"].datetime.len(), 4860);
         assert_eq!(sensor_table["acceleration"].data.len(), 1);
@@ -640,6 +1623,1000 @@ mod tests {
         assert_eq!(sensor_table["battery_voltage"].data.len(), 1);
     }
 
+    #[test]
+    fn test_actigraph_reader_window() {
+        let data = include_bytes!("../../test_data/cmi/actigraph.gt3x");
+        let start = 1714488790 * 1_000_000_000;
+        let end = 1714488800 * 1_000_000_000;
+        let mut reader = ActigraphReader::with_options(sensors::ReadOptions {
+            start: Some(start),
+            end: Some(end),
+            ..sensors::ReadOptions::default()
+        });
+        let mut sensor_table = HashMap::new();
+        let result = reader.read(
+            Cursor::new(data),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
+            },
+            false,
+        );
+        assert!(result.is_ok());
+
+        // 10 seconds at 60 Hz
+        let acceleration = &sensor_table["acceleration"];
+        assert_eq!(acceleration.datetime.len(), 600);
+        assert_eq!(acceleration.datetime[0], start);
+        assert_eq!(
+            acceleration.datetime[599],
+            start + 9 * 1_000_000_000 + 1_000_000_000 / 60 * 59
+        );
+
+        // No low-rate records fall into this window
+        assert!(sensor_table["battery_voltage"].datetime.is_empty());
+        assert!(sensor_table["light"].datetime.is_empty());
+    }
+
+    #[test]
+    fn test_actigraph_reader_sensor_selection() {
+        let data = include_bytes!("../../test_data/cmi/actigraph.gt3x");
+        let mut reader = ActigraphReader::with_options(sensors::ReadOptions {
+            sensors: [sensors::SensorKind::Light].into_iter().collect(),
+            ..sensors::ReadOptions::default()
+        });
+        let mut sensor_table = HashMap::new();
+        let result = reader.read(
+            Cursor::new(data),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
+            },
+            false,
+        );
+        assert!(result.is_ok());
+
+        let names: Vec<_> = sensor_table.keys().copied().collect();
+        assert_eq!(names, ["light"]);
+        assert!(!sensor_table["light"].datetime.is_empty());
+    }
+
+    fn read_idle_sleep(
+        idle_sleep: sensors::IdleSleep,
+        start: Option<i64>,
+    ) -> (sensors::SensorTable, sensors::ReadResult) {
+        let data = include_bytes!("../../test_data/cmi/actigraph.gt3x");
+        let mut reader = ActigraphReader::with_options(sensors::ReadOptions {
+            idle_sleep,
+            start,
+            ..sensors::ReadOptions::default()
+        });
+        let mut acceleration = None;
+        let result = reader
+            .read(
+                Cursor::new(data),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        if table.name == "acceleration" {
+                            acceleration = Some(table);
+                        }
+                    }
+                },
+                false,
+            )
+            .unwrap();
+        (acceleration.unwrap(), result)
+    }
+
+    #[test]
+    fn test_actigraph_idle_sleep() {
+        // The fixture has no record for second 81 and an empty record for second 82.
+        let gap_start = (1714488780 + 81) * NANOS_PER_SECOND;
+        let gap_end = gap_start + 2 * NANOS_PER_SECOND;
+
+        let (acceleration, result) = read_idle_sleep(sensors::IdleSleep::Leave, None);
+        assert_eq!(acceleration.datetime.len(), 4860);
+        assert!(result.filled_spans.is_empty());
+
+        let (acceleration, result) = read_idle_sleep(sensors::IdleSleep::FillLast, None);
+        assert_eq!(acceleration.datetime.len(), 4860 + 2 * 60);
+        assert_eq!(result.filled_spans, [(gap_start, gap_end)]);
+        assert_eq!(acceleration.datetime[4860], gap_start);
+        assert_eq!(
+            acceleration.datetime[4860 + 60],
+            gap_start + NANOS_PER_SECOND
+        );
+        let sensors::SensorDataDyn::F32(data) = &acceleration.data[0].data else {
+            panic!("Expected F32 data");
+        };
+        // Repeats the last sample before the gap
+        for sample in data[4859 * 3..].chunks_exact(3) {
+            assert_eq!(sample, &data[4859 * 3..4860 * 3]);
+        }
+
+        let (acceleration, result) = read_idle_sleep(sensors::IdleSleep::Nan, None);
+        assert_eq!(result.filled_spans, [(gap_start, gap_end)]);
+        let sensors::SensorDataDyn::F32(data) = &acceleration.data[0].data else {
+            panic!("Expected F32 data");
+        };
+        assert!(data[..4860 * 3].iter().all(|v| !v.is_nan()));
+        assert!(data[4860 * 3..].iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn test_actigraph_idle_sleep_window() {
+        // Half-way through the missing second, on a sample
+        let start = (1714488780 + 81) * NANOS_PER_SECOND + 30 * (NANOS_PER_SECOND / 60);
+        let (acceleration, result) = read_idle_sleep(sensors::IdleSleep::FillLast, Some(start));
+
+        assert_eq!(acceleration.datetime.len(), 30 + 60);
+        assert_eq!(acceleration.datetime[0], start);
+        assert_eq!(
+            result.filled_spans,
+            [(start, (1714488780 + 83) * NANOS_PER_SECOND)]
+        );
+        // The last sample before the window is still repeated
+        let sensors::SensorDataDyn::F32(data) = &acceleration.data[0].data else {
+            panic!("Expected F32 data");
+        };
+        assert_approx_eq!(data[0], 0.117188, 1e-6);
+    }
+
+    #[test]
+    fn test_pre_window_activity() {
+        let mut pre_window = PreWindowActivity::default();
+        let mut last_sample = Some([0.0; 3]);
+        pre_window.resolve(&mut last_sample);
+        assert_eq!(last_sample, Some([0.0; 3]));
+
+        let payload = |x: i16| [x, 0, 256].map(i16::to_le_bytes).concat();
+        pre_window.keep(true, 256.0, &payload(128));
+        pre_window.keep(true, 256.0, &payload(512));
+        // Records without samples do not replace the kept one
+        pre_window.keep(false, 256.0, &[]);
+        pre_window.resolve(&mut last_sample);
+        assert_eq!(last_sample, Some([2.0, 0.0, 1.0]));
+
+        pre_window.keep(true, 256.0, &payload(128));
+        pre_window.discard();
+        pre_window.resolve(&mut last_sample);
+        assert_eq!(last_sample, Some([2.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_actigraph_info() {
+        let data = include_bytes!("../../test_data/cmi/actigraph.gt3x");
+        let mut metadata = HashMap::new();
+        let info = ActigraphReader::new()
+            .info(Cursor::new(data), |entry| {
+                metadata.insert(
+                    (entry.category.to_owned(), entry.key.to_owned()),
+                    entry.value.into_owned(),
+                );
+            })
+            .unwrap();
+
+        assert_eq!(
+            metadata[&("info".into(), "Sample Rate".into())],
+            MetadataValue::Int(60)
+        );
+        assert_eq!(
+            metadata[&("device_feature_enabled".into(), "no_raw_data".into())],
+            MetadataValue::Bool(false)
+        );
+        assert!(metadata.contains_key(&("metadata".into(), "metadata_3".into())));
+
+        assert_eq!(info.first_timestamp, Some(1714488780 * 1_000_000_000));
+        // "Last Sample Time" from info.txt
+        assert_eq!(info.last_timestamp, Some(1714488900 * 1_000_000_000));
+        assert_eq!(info.sample_rate, Some(60.0));
+        assert_eq!(info.estimated_samples, Some(120 * 60 + 1));
+        assert_eq!(
+            info.sensors,
+            [
+                sensors::SensorKind::Accelerometer,
+                sensors::SensorKind::Light,
+                sensors::SensorKind::Capacitive,
+                sensors::SensorKind::BatteryVoltage,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_actigraph_info_logged_sensors() {
+        let mut log = test_log(&[(0x1A, TEST_START, vec![0; 6 * 30])]);
+        // Garbage between records is skipped
+        log.extend([0x00, 0xFF, 0x00]);
+        log.extend(build_log(&[
+            (0x02, TEST_START + 60, vec![0x10, 0x0E]),
+            // Too late to be seen
+            (0x05, TEST_START + 11 * 60, vec![0x00, 0x01]),
+        ]));
+        let data = test_gt3x(&log);
+
+        let info = ActigraphReader::new()
+            .info(Cursor::new(data), |_| {})
+            .unwrap();
+        assert_eq!(
+            info.first_timestamp,
+            Some(TEST_START as i64 * NANOS_PER_SECOND)
+        );
+        assert_eq!(
+            info.sensors,
+            [
+                sensors::SensorKind::Accelerometer,
+                sensors::SensorKind::BatteryVoltage,
+            ]
+        );
+    }
+
+    const TEST_INFO_TXT: &str = "Serial Number: TAS1E23456789\r\n\
+        Device Type: wGT3XBT\r\n\
+        Sample Rate: 30\r\n\
+        Start Date: 638500855800000000\r\n\
+        Last Sample Time: 638500855900000000\r\n";
+
+    /// Parameters payload with a sample rate of 30 Hz and an accel scale of 256
+    fn test_parameters() -> Vec<u8> {
+        let mut payload = Vec::new();
+        for (address_space, identifier, value) in
+            [(1u32, 10u32, 30u32), (0, 55, ssp_codec::encode(256.0))]
+        {
+            payload.extend((identifier << 16 | address_space).to_le_bytes());
+            payload.extend(value.to_le_bytes());
+        }
+        payload
+    }
+
+    /// Start of the synthetic recordings (Unix time)
+    const TEST_START: u32 = 1714488780;
+
+    /// Build `log.bin` of a synthetic recording: a parameters record (see
+    /// [`test_parameters`]) at [`TEST_START`] followed by `records`
+    fn test_log(records: &[(u8, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut log = build_log(&[(0x15, TEST_START, test_parameters())]);
+        log.extend(build_log(records));
+        log
+    }
+
+    /// Build a GT3X archive from [`TEST_INFO_TXT`] and `log`
+    fn test_gt3x(log: &[u8]) -> Vec<u8> {
+        build_archive(&[
+            (GT3X_FILE_INFO, TEST_INFO_TXT.as_bytes()),
+            (GT3X_FILE_LOG, log),
+        ])
+    }
+
+    /// Build `log.bin` from `(type, unix time, payload)` records
+    fn build_log(records: &[(u8, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut log = Vec::new();
+        for (record_type, timestamp, payload) in records {
+            let start = log.len();
+            log.push(0x1E);
+            log.push(*record_type);
+            log.extend(timestamp.to_le_bytes());
+            log.extend((payload.len() as u16).to_le_bytes());
+            log.extend(payload);
+            let checksum = !log[start..].iter().fold(0u8, |acc, b| acc ^ b);
+            log.push(checksum);
+        }
+        log
+    }
+
+    /// Build a ZIP archive from `(name, contents)` pairs
+    fn build_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (name, contents) in files {
+            zip.start_file(*name, options).unwrap();
+            std::io::Write::write_all(&mut zip, contents).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn read_tables(data: Vec<u8>) -> HashMap<&'static str, sensors::SensorTable> {
+        let mut sensor_table = HashMap::new();
+        ActigraphReader::new()
+            .read(
+                Cursor::new(data),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        sensor_table.insert(table.name, table);
+                    }
+                },
+                false,
+            )
+            .unwrap();
+        sensor_table
+    }
+
+    #[test]
+    fn test_actigraph_activity2() {
+        let mut records = Vec::new();
+        for second in 0..2 {
+            let mut payload = Vec::new();
+            for i in 0..30i16 {
+                for value in [256 + i, -128, 512 * (second as i16 + 1)] {
+                    payload.extend(value.to_le_bytes());
+                }
+            }
+            records.push((0x1A, TEST_START + second, payload));
+        }
+        let sensor_table = read_tables(test_gt3x(&test_log(&records)));
+
+        let acceleration = &sensor_table["acceleration"];
+        assert_eq!(acceleration.datetime.len(), 60);
+        assert_eq!(acceleration.datetime[0], TEST_START as i64 * 1_000_000_000);
+        assert_eq!(
+            acceleration.datetime[31],
+            (TEST_START as i64 + 1) * 1_000_000_000 + 1_000_000_000 / 30
+        );
+        let sensors::SensorDataDyn::F32(data) = &acceleration.data[0].data else {
+            panic!("Expected F32 data");
+        };
+        assert_eq!(&data[0..3], &[1.0, -0.5, 2.0]);
+        assert_eq!(&data[3..6], &[257.0 / 256.0, -0.5, 2.0]);
+        assert_eq!(&data[59 * 3..], &[285.0 / 256.0, -0.5, 4.0]);
+    }
+
+    #[test]
+    fn test_actigraph_activity2_device_data() {
+        // Re-encode the activity records of the device recording as
+        // Activity2 records, which must decode to the same samples.
+        let data = include_bytes!("../../test_data/cmi/actigraph.gt3x");
+        let mut archive = zip::ZipArchive::new(Cursor::new(&data[..])).unwrap();
+        let mut files = Vec::new();
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index).unwrap();
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents).unwrap();
+            files.push((file.name().to_string(), contents));
+        }
+
+        let (_, log) = files
+            .iter_mut()
+            .find(|(name, _)| name == GT3X_FILE_LOG)
+            .unwrap();
+        let mut records = Vec::new();
+        let mut record_data = [0u8; u16::MAX as usize + 1];
+        let mut it = LogRecordIterator::new(log.as_slice());
+        while let Some(record) = it.next(&mut record_data) {
+            let (header, record_data) = record.unwrap();
+            let payload = &record_data[..record_data.len() - 1];
+            let (record_type, payload) = match LogRecordType::from_u8(header.record_type) {
+                LogRecordType::Activity => (
+                    0x1A,
+                    activity_samples(payload)
+                        .flatten()
+                        .flat_map(i16::to_le_bytes)
+                        .collect(),
+                ),
+                _ => (header.record_type, payload.to_vec()),
+            };
+            records.push((record_type, header.timestamp, payload));
+        }
+        assert!(
+            records
+                .iter()
+                .any(|&(record_type, _, _)| record_type == 0x1A)
+        );
+        *log = build_log(&records);
+
+        let files: Vec<_> = files
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice()))
+            .collect();
+        let converted = read_tables(build_archive(&files));
+        let original = read_tables(data.to_vec());
+        assert_eq!(converted["acceleration"], original["acceleration"]);
+        assert_eq!(converted["acceleration"].datetime.len(), 4860);
+    }
+
+    #[test]
+    fn test_actigraph_imu() {
+        let mut parameters = test_parameters();
+        for (identifier, value) in [(50u32, 16.4), (51, 0.6), (57, 333.87), (58, 21.0)] {
+            parameters.extend((identifier << 16).to_le_bytes());
+            parameters.extend(ssp_codec::encode(value).to_le_bytes());
+        }
+
+        // Schema 1: 4 samples per second of seven signed LE 16-bit columns
+        let mut schema = vec![1, 0, 7, 0, 4, 0];
+        let labels = [
+            "Gyroscope X",
+            "Gyroscope Y",
+            "Gyroscope Z",
+            "Magnetometer X",
+            "Magnetometer Y",
+            "Magnetometer Z",
+            "Temperature",
+        ];
+        for (i, label) in labels.iter().enumerate() {
+            schema.extend([2, i as u8 * 16, 16]);
+            schema.extend(ssp_codec::encode(1.0).to_le_bytes());
+            let mut label = label.as_bytes().to_vec();
+            label.resize(16, 0);
+            schema.extend(label);
+        }
+
+        let mut records = vec![(0x15, TEST_START, parameters), (0x18, TEST_START, schema)];
+        for second in 0..2 {
+            let mut payload = vec![1, 0];
+            for i in 0..4i16 {
+                for value in [164 * i, -164, 0, 6, 12, -6, 3339 + second as i16] {
+                    payload.extend(value.to_le_bytes());
+                }
+            }
+            records.push((0x19, TEST_START + second, payload));
+        }
+        let data = test_gt3x(&build_log(&records));
+        let sensor_table = read_tables(data.clone());
+
+        let imu = &sensor_table["imu"];
+        assert_eq!(imu.datetime.len(), 8);
+        assert_eq!(
+            imu.datetime[1],
+            TEST_START as i64 * NANOS_PER_SECOND + 250_000_000
+        );
+        assert_eq!(imu.datetime[4], (TEST_START as i64 + 1) * NANOS_PER_SECOND);
+        let kinds: Vec<_> = imu.data.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                sensors::SensorKind::Gyroscope,
+                sensors::SensorKind::Magnetometer,
+                sensors::SensorKind::Temperature
+            ]
+        );
+        let sensors::SensorDataDyn::F32(gyroscope) = &imu.data[0].data else {
+            panic!("Expected F32 data");
+        };
+        assert_eq!(gyroscope.len(), 8 * 3);
+        assert_approx_eq!(gyroscope[3], 10.0, 1e-3);
+        assert_approx_eq!(gyroscope[4], -10.0, 1e-3);
+        let sensors::SensorDataDyn::F32(magnetometer) = &imu.data[1].data else {
+            panic!("Expected F32 data");
+        };
+        assert_approx_eq!(magnetometer[0], 10.0, 1e-3);
+        assert_approx_eq!(magnetometer[2], -10.0, 1e-3);
+        let sensors::SensorDataDyn::F32(temperature) = &imu.data[2].data else {
+            panic!("Expected F32 data");
+        };
+        assert_eq!(temperature.len(), 8);
+        assert_approx_eq!(temperature[0], 3339.0 / 333.87 + 21.0, 1e-3);
+
+        // Files without a schema have no IMU table
+        assert!(!read_tables(test_gt3x(&build_log(&records[..1]))).contains_key("imu"));
+
+        let info = ActigraphReader::new()
+            .info(Cursor::new(data), |_| {})
+            .unwrap();
+        assert!(info.sensors.contains(&sensors::SensorKind::Magnetometer));
+    }
+
+    #[test]
+    fn test_actigraph_epochs() {
+        let mut records = Vec::new();
+        for minute in 0..3u16 {
+            let mut payload = Vec::new();
+            for value in [100 * minute, 2, 3, 10 + minute] {
+                payload.extend(value.to_le_bytes());
+            }
+            payload.push(minute as u8);
+            records.push((0x0F, TEST_START + 60 * minute as u32, payload));
+        }
+        let data = test_gt3x(&test_log(&records));
+
+        let epochs = &read_tables(data.clone())["epoch_counts"];
+        assert_eq!(
+            epochs.datetime,
+            [0, 60, 120].map(|offset| (TEST_START as i64 + offset) * NANOS_PER_SECOND)
+        );
+        assert_eq!(
+            epochs.data,
+            [
+                sensors::SensorData {
+                    kind: sensors::SensorKind::ActivityCounts,
+                    data: sensors::SensorDataDyn::U32(vec![0, 2, 3, 100, 2, 3, 200, 2, 3]),
+                },
+                sensors::SensorData {
+                    kind: sensors::SensorKind::Steps,
+                    data: sensors::SensorDataDyn::U32(vec![10, 11, 12]),
+                },
+                sensors::SensorData {
+                    kind: sensors::SensorKind::Inclinometer,
+                    data: sensors::SensorDataDyn::U8(vec![0, 1, 2]),
+                },
+            ]
+        );
+
+        // Only the selected columns are decoded
+        let mut tables = Vec::new();
+        ActigraphReader::with_options(sensors::ReadOptions {
+            sensors: [sensors::SensorKind::Steps].into_iter().collect(),
+            ..Default::default()
+        })
+        .read(
+            Cursor::new(data.clone()),
+            |_| {},
+            |chunk| tables.extend(chunk),
+            false,
+        )
+        .unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].data.len(), 1);
+        assert_eq!(tables[0].data[0].kind, sensors::SensorKind::Steps);
+
+        let info = ActigraphReader::new()
+            .info(Cursor::new(data), |_| {})
+            .unwrap();
+        assert_eq!(
+            info.first_timestamp,
+            Some(TEST_START as i64 * NANOS_PER_SECOND)
+        );
+        assert!(info.sensors.contains(&sensors::SensorKind::ActivityCounts));
+
+        // Files without epoch records have no epoch table
+        assert!(!read_tables(test_gt3x(&test_log(&[]))).contains_key("epoch_counts"));
+    }
+
+    #[test]
+    fn test_actigraph_mixed_epochs() {
+        let data = test_gt3x(&test_log(&[
+            (0x09, TEST_START, vec![1, 0, 2, 0, 3, 0]),
+            (0x0F, TEST_START + 60, vec![4, 0, 5, 0, 6, 0, 0, 0, 0]),
+        ]));
+        let epochs = &read_tables(data)["epoch_counts"];
+        assert_eq!(
+            epochs.data[1..],
+            [
+                sensors::SensorData {
+                    kind: sensors::SensorKind::Steps,
+                    data: sensors::SensorDataDyn::U32(vec![epoch::MISSING_STEPS, 0]),
+                },
+                sensors::SensorData {
+                    kind: sensors::SensorKind::Inclinometer,
+                    data: sensors::SensorDataDyn::U8(vec![epoch::MISSING_INCLINOMETER, 0]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_actigraph_chunks_late_epochs() {
+        // Five seconds of Activity2 records, then an epoch record.
+        let mut records: Vec<_> = (0..5)
+            .map(|second| {
+                let payload = [256i16, 0, 0].repeat(30);
+                let payload = payload.iter().flat_map(|v| v.to_le_bytes()).collect();
+                (0x1A, TEST_START + second, payload)
+            })
+            .collect();
+        records.push((0x0F, TEST_START + 4, vec![1, 0, 2, 0, 3, 0, 10, 0, 1]));
+        let data = test_gt3x(&test_log(&records));
+
+        let mut chunks = Vec::new();
+        ActigraphReader::with_options(sensors::ReadOptions {
+            chunk_samples: Some(30),
+            ..sensors::ReadOptions::default()
+        })
+        .read(
+            Cursor::new(data.clone()),
+            |_| {},
+            |chunk| chunks.push(chunk),
+            false,
+        )
+        .unwrap();
+
+        // The epoch table only appears with the chunk of the epoch record.
+        let has_epochs: Vec<_> = chunks
+            .iter()
+            .map(|chunk| chunk.iter().any(|table| table.name == "epoch_counts"))
+            .collect();
+        assert_eq!(has_epochs, [false, false, false, false, false, true]);
+
+        let whole = read_tables(data);
+        let mut acceleration = Vec::new();
+        let mut epochs = Vec::new();
+        for table in chunks.into_iter().flatten() {
+            match table.name {
+                "acceleration" => acceleration.extend(table.datetime),
+                "epoch_counts" => epochs.extend(table.datetime),
+                _ => {}
+            }
+        }
+        assert_eq!(acceleration, whole["acceleration"].datetime);
+        assert_eq!(epochs, whole["epoch_counts"].datetime);
+    }
+
+    #[test]
+    fn test_actigraph_heart_rate() {
+        let records = vec![
+            (0x04, TEST_START, vec![70]),
+            (0x0E, TEST_START + 1, vec![0x10, 72, 0x00, 0x03, 0x33, 0x03]),
+        ];
+        let data = test_gt3x(&test_log(&records));
+
+        let heart_rate = &read_tables(data)["heart_rate"];
+        let start = TEST_START as i64 * NANOS_PER_SECOND;
+        assert_eq!(
+            heart_rate.datetime,
+            [start, start + NANOS_PER_SECOND, start + NANOS_PER_SECOND]
+        );
+        assert_eq!(
+            heart_rate.data[0],
+            sensors::SensorData {
+                kind: sensors::SensorKind::HeartRate,
+                data: sensors::SensorDataDyn::U16(vec![70, 72, 72]),
+            }
+        );
+        let sensors::SensorDataDyn::F32(rr_interval) = &heart_rate.data[1].data else {
+            panic!("Expected F32 data");
+        };
+        assert!(rr_interval[0].is_nan());
+        assert_approx_eq!(rr_interval[1], 0.75);
+        assert_approx_eq!(rr_interval[2], 0.8, 1e-3);
+    }
+
+    #[test]
+    fn test_actigraph_fifo_error() {
+        let records = vec![
+            (0x07, TEST_START, b"tag".to_vec()),
+            (0x13, TEST_START + 1, vec![0x01, 0x02]),
+        ];
+        let data = test_gt3x(&test_log(&records));
+        let result = ActigraphReader::new()
+            .read(Cursor::new(data.clone()), |_| {}, |_| {}, false)
+            .unwrap();
+
+        assert_eq!(
+            result.warnings,
+            ["device reported a FIFO error at 2024-04-30T14:53:01+00:00 (payload 0102)"]
+        );
+        let kinds: Vec<_> = result.events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, ["tag", "fifo_error"]);
+        assert_eq!(result.events[0].payload, b"tag");
+
+        // Events outside the time window are dropped
+        let result = ActigraphReader::with_options(sensors::ReadOptions {
+            end: Some((TEST_START as i64 + 1) * NANOS_PER_SECOND),
+            ..Default::default()
+        })
+        .read(Cursor::new(data), |_| {}, |_| {}, false)
+        .unwrap();
+        assert!(result.warnings.is_empty());
+        assert_eq!(result.events.len(), 1);
+    }
+
+    /// Legacy `activity.bin` of 75 samples (2.5 s at 30 Hz) of 12-bit Y, X, Z
+    /// values `(i, -i, 341)`
+    fn legacy_activity() -> Vec<u8> {
+        let mut bits = Vec::new();
+        for i in 0..75i16 {
+            for value in [i, -i, 341] {
+                bits.extend((0..12).rev().map(|bit| (value >> bit) & 1 == 1));
+            }
+        }
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .fold(0u8, |acc, (i, &bit)| acc | (bit as u8) << (7 - i))
+            })
+            .collect()
+    }
+
+    /// Build a legacy GT3X archive from `activity.bin` and `lux.bin`
+    fn legacy_gt3x(activity: &[u8], lux: &[u8]) -> Vec<u8> {
+        let info_txt = format!("{}Acceleration Scale: 341.0\r\n", TEST_INFO_TXT);
+        build_archive(&[
+            (GT3X_FILE_INFO, info_txt.as_bytes()),
+            (GT3X_FILE_ACTIVITY, activity),
+            (GT3X_FILE_LUX, lux),
+        ])
+    }
+
+    #[test]
+    fn test_actigraph_legacy() {
+        let lux = [10u16, 20, 30].map(u16::to_le_bytes).concat();
+        let data = legacy_gt3x(&legacy_activity(), &lux);
+        let start = TEST_START as i64 * NANOS_PER_SECOND;
+
+        let sensor_table = read_tables(data.clone());
+        let acceleration = &sensor_table["acceleration"];
+        assert_eq!(acceleration.datetime.len(), 75);
+        assert_eq!(
+            acceleration.datetime[31],
+            start + NANOS_PER_SECOND + 33_333_333
+        );
+        let sensors::SensorDataDyn::F32(values) = &acceleration.data[0].data else {
+            panic!("Expected F32 data");
+        };
+        assert_approx_eq!(values[74 * 3], -74.0 / 341.0);
+        assert_approx_eq!(values[74 * 3 + 1], 74.0 / 341.0);
+        assert_approx_eq!(values[74 * 3 + 2], 1.0);
+        let light = &sensor_table["light"];
+        assert_eq!(
+            light.datetime,
+            [0, 1, 2].map(|s| start + s * NANOS_PER_SECOND)
+        );
+        assert_eq!(
+            light.data[0].data,
+            sensors::SensorDataDyn::U16(vec![10, 20, 30])
+        );
+
+        // Chunks stay time-ordered
+        let mut light_times = Vec::new();
+        let mut acceleration_samples = 0;
+        ActigraphReader::with_options(sensors::ReadOptions {
+            chunk_samples: Some(1),
+            ..Default::default()
+        })
+        .read(
+            Cursor::new(data.clone()),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    match table.name {
+                        "light" => light_times.extend(table.datetime),
+                        "acceleration" => acceleration_samples += table.datetime.len(),
+                        _ => {}
+                    }
+                }
+            },
+            false,
+        )
+        .unwrap();
+        assert_eq!(acceleration_samples, 75);
+        assert_eq!(light_times, light.datetime);
+
+        let info = ActigraphReader::new()
+            .info(Cursor::new(data), |_| {})
+            .unwrap();
+        assert_eq!(
+            info.sensors,
+            [
+                sensors::SensorKind::Accelerometer,
+                sensors::SensorKind::Light
+            ]
+        );
+    }
+
+    #[test]
+    fn test_actigraph_legacy_truncated() {
+        let mut activity = legacy_activity();
+        activity.extend([0xAB, 0xCD]);
+        let mut lux = [10u16, 20, 30].map(u16::to_le_bytes).concat();
+        lux.push(40);
+        let data = legacy_gt3x(&activity, &lux);
+
+        let error = ActigraphReader::new()
+            .read(Cursor::new(data.clone()), |_| {}, |_| {}, false)
+            .unwrap_err();
+        assert!(matches!(error, ActfastError::UnexpectedEof { .. }));
+
+        let mut sensor_table = HashMap::new();
+        let result = ActigraphReader::new()
+            .read(
+                Cursor::new(data),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        sensor_table.insert(table.name, table);
+                    }
+                },
+                true,
+            )
+            .unwrap();
+        assert_eq!(
+            result.warnings,
+            [
+                "Unexpected end of file in lux.bin (incomplete sample) at byte offset 7",
+                "Unexpected end of file in activity.bin (incomplete sample) at byte offset 340",
+            ]
+        );
+        assert_eq!(sensor_table["acceleration"].datetime.len(), 75);
+        assert_eq!(sensor_table["light"].datetime.len(), 3);
+    }
+
+    #[test]
+    fn test_actigraph_legacy_idle_sleep() {
+        // Without timestamps in the stream there are no gaps to fill
+        let data = legacy_gt3x(&legacy_activity(), &[]);
+        let mut acceleration = Vec::new();
+        let result = ActigraphReader::with_options(sensors::ReadOptions {
+            idle_sleep: sensors::IdleSleep::FillLast,
+            ..Default::default()
+        })
+        .read(
+            Cursor::new(data.clone()),
+            |_| {},
+            |tables| acceleration.extend(tables.into_iter().filter(|t| t.name == "acceleration")),
+            false,
+        )
+        .unwrap();
+        assert!(result.filled_spans.is_empty());
+        assert_eq!(
+            acceleration,
+            [read_tables(data).remove("acceleration").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_actigraph_resync() {
+        let mut log = test_log(&[(0x05, TEST_START, vec![1, 0])]);
+        let corrupt_from = log.len();
+        // Garbage with separators: an unknown record type, a wrong checksum
+        // and a record going back in time
+        log.extend([0x00, 0x1E, 0xFF, 0, 0, 0, 0, 0, 0, 0xAB]);
+        let mut bad_checksum = build_log(&[(0x05, TEST_START + 1, vec![9, 0])]);
+        *bad_checksum.last_mut().unwrap() ^= 1;
+        log.extend(bad_checksum);
+        log.extend(build_log(&[(0x05, TEST_START - 1, vec![8, 0])]));
+        let corrupt_to = log.len();
+        log.extend(build_log(&[
+            (0x05, TEST_START + 2, vec![2, 0]),
+            (0x05, TEST_START + 3, vec![3, 0]),
+        ]));
+        let data = test_gt3x(&log);
+
+        let mut light = Vec::new();
+        let result = ActigraphReader::new()
+            .read(
+                Cursor::new(data.clone()),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        if table.name == "light" {
+                            light.push(table);
+                        }
+                    }
+                },
+                true,
+            )
+            .unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        assert!(
+            result.warnings[0].contains(&format!("skipped bytes {}..{}", corrupt_from, corrupt_to)),
+            "{}",
+            result.warnings[0]
+        );
+        assert_eq!(
+            light[0].data[0].data,
+            sensors::SensorDataDyn::U16(vec![1, 2, 3])
+        );
+
+        // Strict mode still fails at the first corrupt byte
+        let error = ActigraphReader::new()
+            .read(Cursor::new(data), |_| {}, |_| {}, false)
+            .unwrap_err();
+        assert_eq!(
+            error.location().and_then(|location| location.byte_offset),
+            Some(corrupt_from as u64 + 8)
+        );
+    }
+
+    #[test]
+    fn test_actigraph_resync_long_garbage() {
+        let mut log = test_log(&[(0x05, TEST_START, vec![1, 0])]);
+        let corrupt_from = log.len();
+        // Plausible lux headers (timestamps after the last record) with
+        // 256 byte payloads that fail the checksum, over several windows
+        let candidate = [0x1E, 0x05, 0, 0, 0, 0x70, 0xFF, 0x00];
+        log.push(0x00);
+        while log.len() < corrupt_from + 3 * RESYNC_READ_SIZE + 5 {
+            log.extend(candidate);
+        }
+        let corrupt_to = log.len();
+        log.extend(build_log(&[(0x05, TEST_START + 1, vec![2, 0])]));
+
+        let mut record_data = [0u8; u16::MAX as usize + 1];
+        let mut it = LogRecordIterator::new(log.as_slice());
+        assert!(it.next(&mut record_data).unwrap().is_ok());
+        assert!(it.next(&mut record_data).unwrap().is_ok());
+        assert!(it.next(&mut record_data).unwrap().is_err());
+        assert_eq!(
+            it.resync(u16::MAX as usize + 1),
+            Some((corrupt_from as u64, corrupt_to as u64))
+        );
+        let (header, data) = it.next(&mut record_data).unwrap().unwrap();
+        assert_eq!(header.timestamp, TEST_START + 1);
+        assert_eq!(&data[..2], [2, 0]);
+        assert!(it.next(&mut record_data).is_none());
+    }
+
+    #[test]
+    fn test_actigraph_checksum() {
+        let mut log = test_log(&[(0x05, TEST_START, vec![1, 0])]);
+        let corrupt_offset = log.len();
+        let mut corrupt = build_log(&[(0x05, TEST_START + 1, vec![2, 0])]);
+        corrupt[8] ^= 0x40; // Flip a payload bit
+        log.extend(corrupt);
+        log.extend(build_log(&[(0x05, TEST_START + 2, vec![3, 0])]));
+        let data = test_gt3x(&log);
+
+        let error = ActigraphReader::new()
+            .read(Cursor::new(data.clone()), |_| {}, |_| {}, false)
+            .unwrap_err();
+        let location = error.location().unwrap();
+        assert_eq!(location.byte_offset, Some(corrupt_offset as u64));
+        assert_eq!(location.record_index, Some(2));
+
+        let mut light = Vec::new();
+        let result = ActigraphReader::new()
+            .read(
+                Cursor::new(data),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        if table.name == "light" {
+                            light.push(table);
+                        }
+                    }
+                },
+                true,
+            )
+            .unwrap();
+        assert_eq!(result.checksum_mismatches, 1);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("checksum"));
+        assert_eq!(
+            light[0].data[0].data,
+            sensors::SensorDataDyn::U16(vec![1, 3])
+        );
+    }
+
+    #[test]
+    fn test_actigraph_idle_sleep_after_skipped_record() {
+        // Activity2 records for seconds 0 to 2 and 4, the one for second 1
+        // with a bad checksum: only second 3 was spent in idle sleep.
+        let record = |second: u32| {
+            let payload = [256i16, 0, 0].repeat(30);
+            let payload = payload.iter().flat_map(|v| v.to_le_bytes()).collect();
+            (0x1A, TEST_START + second, payload)
+        };
+        let mut log = test_log(&[record(0)]);
+        let mut corrupt = build_log(&[record(1)]);
+        corrupt[8] ^= 0x40;
+        log.extend(corrupt);
+        log.extend(build_log(&[record(2), record(4)]));
+
+        let mut acceleration = Vec::new();
+        let result = ActigraphReader::with_options(sensors::ReadOptions {
+            idle_sleep: sensors::IdleSleep::FillLast,
+            ..sensors::ReadOptions::default()
+        })
+        .read(
+            Cursor::new(test_gt3x(&log)),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    if table.name == "acceleration" {
+                        acceleration.extend(table.datetime);
+                    }
+                }
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(result.checksum_mismatches, 1);
+        let second = |s: u32| (TEST_START + s) as i64 * NANOS_PER_SECOND;
+        assert_eq!(result.filled_spans, [(second(3), second(4))]);
+        assert_eq!(acceleration.len(), 4 * 30);
+        assert!(
+            !acceleration
+                .iter()
+                .any(|&t| (second(1)..second(2)).contains(&t))
+        );
+    }
+
     #[test]
     fn test_invalid_zip() {
         let mut reader = ActigraphReader::new();
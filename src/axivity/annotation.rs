@@ -0,0 +1,149 @@
+//! Study and subject details that OMGUI stores in the header annotation as
+//! URL-encoded key/value pairs, such as `?_c=Centre&_s=Study&_sh=172`.
+//! <https://github.com/digitalinteraction/openmovement-python/blob/master/openmovement/load/cwa_load.py>
+
+/// OMGUI keys with the metadata category and key they are emitted under
+const KNOWN_KEYS: [(&str, &str, &str); 18] = [
+    ("_c", "study", "centre"),
+    ("_s", "study", "code"),
+    ("_i", "study", "investigator"),
+    ("_x", "study", "exercise_code"),
+    ("_sc", "study", "setup_operator"),
+    ("_n", "study", "notes"),
+    ("_b", "study", "start_time"),
+    ("_e", "study", "end_time"),
+    ("_ro", "study", "recovery_operator"),
+    ("_r", "study", "retrieval_time"),
+    ("_co", "study", "comments"),
+    ("_so", "subject", "volunteer_number"),
+    ("_p", "subject", "body_location"),
+    ("_sh", "subject", "height"),
+    ("_sw", "subject", "weight"),
+    ("_ss", "subject", "sex"),
+    ("_ha", "subject", "handedness"),
+    ("_se", "subject", "site"),
+];
+
+/// Category of keys OMGUI does not define, so they cannot collide with the
+/// readable names of the known ones
+const UNKNOWN_CATEGORY: &str = "annotation";
+
+/// A decoded annotation field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub category: &'static str,
+    /// Readable name of a known key, the decoded key itself otherwise
+    pub key: String,
+    pub value: String,
+}
+
+impl Field {
+    /// Returns `true` for fields holding a number, such as the subject height
+    pub fn is_numeric(&self) -> bool {
+        self.category == "subject" && matches!(self.key.as_str(), "height" | "weight")
+    }
+}
+
+/// Decode `+` and `%XX` escapes, keeping malformed escapes as they are
+fn url_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', None) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, None) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse an annotation of URL-encoded key/value pairs. Returns `None` for
+/// free text, i.e. if it neither starts with `?` nor with an OMGUI key, or
+/// any part is not a `key=value` pair.
+pub fn parse(annotation: &str) -> Option<Vec<Field>> {
+    let query = match annotation.strip_prefix('?') {
+        Some(query) => query,
+        None if annotation.starts_with('_') => annotation,
+        None => return None,
+    };
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let key = url_decode(key);
+            let (category, key) = match KNOWN_KEYS.iter().find(|(raw, _, _)| *raw == key) {
+                Some(&(_, category, name)) => (category, name.to_string()),
+                None => (UNKNOWN_CATEGORY, key),
+            };
+            Some(Field {
+                category,
+                key,
+                value: url_decode(value),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(
+            url_decode("Left+wrist%2C%20dominant"),
+            "Left wrist, dominant"
+        );
+        assert_eq!(url_decode("caf%C3%A9"), "café");
+        assert_eq!(url_decode("100%"), "100%");
+        assert_eq!(url_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn test_parse() {
+        let fields =
+            parse("?_c=Newcastle&_s=ST01&_sh=172.5&_ha=2&_se=Newcastle&_xy=a%3Db").unwrap();
+        let field = |category, key: &str, value: &str| Field {
+            category,
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        assert_eq!(
+            fields,
+            [
+                field("study", "centre", "Newcastle"),
+                field("study", "code", "ST01"),
+                field("subject", "height", "172.5"),
+                field("subject", "handedness", "2"),
+                field("subject", "site", "Newcastle"),
+                field("annotation", "_xy", "a=b"),
+            ]
+        );
+        assert!(fields[2].is_numeric());
+        assert!(!fields[3].is_numeric());
+
+        assert_eq!(
+            parse("_n=free&"),
+            Some(vec![field("study", "notes", "free")])
+        );
+        assert_eq!(parse("Subject 12, left wrist"), None);
+        assert_eq!(parse("height=172"), None);
+        assert_eq!(parse("?_c=Newcastle&oops"), None);
+    }
+}
@@ -12,6 +12,17 @@ pub const HW_AX3_DEFAULT: u8 = 0x00;
 pub const HW_AX3_ALT: u8 = 0xFF;
 pub const HW_AX6: u8 = 0x64;
 
+/// Flags of the data sector events byte (offset 22) and their event kinds
+pub const DATA_EVENTS: [(u8, &str); 7] = [
+    (0x01, "resume"),
+    (0x02, "single_tap"),
+    (0x04, "double_tap"),
+    (0x08, "event"),
+    (0x10, "fifo_overflow"),
+    (0x20, "buffer_overflow"),
+    (0x40, "unhandled_interrupt"),
+];
+
 /// Decode a packed CWA timestamp (uint32, device local time) into a `chrono::DateTime<Utc>`.
 ///
 /// Layout:
@@ -4,45 +4,64 @@
 //   https://github.com/digitalinteraction/openmovement/blob/master/Docs/ax3/ax3-technical.md
 //   wadpac/GGIRread `readAxivity.R` (Mirkes / Jackson)
 
+mod annotation;
 mod defs;
 
 use crate::axivity::defs::*;
 use crate::error::{ActfastError, FileLocation, Result};
 use crate::file_format::FileFormat;
-use crate::sensors;
+use crate::sensors::{self, MetadataValue};
 
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 
 #[derive(Default)]
 pub struct HighFrequencyData {
     pub time: Vec<i64>,
     pub acceleration: Vec<f32>,
     pub gyroscope: Vec<f32>,
+    pub magnetometer: Vec<f32>,
 }
 
 impl HighFrequencyData {
-    pub fn reserve(&mut self, samples: usize, has_gyro: bool) {
+    pub const SENSORS: [sensors::SensorKind; 3] = [
+        sensors::SensorKind::Accelerometer,
+        sensors::SensorKind::Gyroscope,
+        sensors::SensorKind::MagnetometerCounts,
+    ];
+
+    fn reserve(&mut self, samples: usize, columns: &Columns, selected: sensors::SensorSet) {
         self.time.reserve(samples);
-        self.acceleration.reserve(samples * 3);
-        if has_gyro {
+        if selected.contains(sensors::SensorKind::Accelerometer) {
+            self.acceleration.reserve(samples * 3);
+        }
+        if columns.gyro && selected.contains(sensors::SensorKind::Gyroscope) {
             self.gyroscope.reserve(samples * 3);
         }
+        if columns.magnetometer && selected.contains(sensors::SensorKind::MagnetometerCounts) {
+            self.magnetometer.reserve(samples * 3);
+        }
     }
 
-    pub fn sensor_table(&self) -> sensors::SensorTable<'_> {
+    pub fn take_sensor_table(&mut self) -> sensors::SensorTable {
         let mut data = vec![sensors::SensorData {
             kind: sensors::SensorKind::Accelerometer,
-            data: sensors::SensorDataDyn::F32(&self.acceleration),
+            data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.acceleration)),
         }];
         if !self.gyroscope.is_empty() {
             data.push(sensors::SensorData {
                 kind: sensors::SensorKind::Gyroscope,
-                data: sensors::SensorDataDyn::F32(&self.gyroscope),
+                data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.gyroscope)),
+            });
+        }
+        if !self.magnetometer.is_empty() {
+            data.push(sensors::SensorData {
+                kind: sensors::SensorKind::MagnetometerCounts,
+                data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.magnetometer)),
             });
         }
         sensors::SensorTable {
             name: "high_frequency",
-            datetime: &self.time,
+            datetime: std::mem::take(&mut self.time),
             data,
         }
     }
@@ -57,6 +76,12 @@ pub struct LowFrequencyData {
 }
 
 impl LowFrequencyData {
+    pub const SENSORS: [sensors::SensorKind; 3] = [
+        sensors::SensorKind::Light,
+        sensors::SensorKind::Temperature,
+        sensors::SensorKind::BatteryVoltage,
+    ];
+
     pub fn reserve(&mut self, sectors: usize) {
         self.time.reserve(sectors);
         self.light.reserve(sectors);
@@ -64,32 +89,35 @@ impl LowFrequencyData {
         self.battery_voltage.reserve(sectors);
     }
 
-    pub fn sensor_table(&self) -> sensors::SensorTable<'_> {
+    pub fn take_sensor_table(&mut self) -> sensors::SensorTable {
         sensors::SensorTable {
             name: "low_frequency",
-            datetime: &self.time,
+            datetime: std::mem::take(&mut self.time),
             data: vec![
                 sensors::SensorData {
                     kind: sensors::SensorKind::Light,
-                    data: sensors::SensorDataDyn::U16(&self.light),
+                    data: sensors::SensorDataDyn::U16(std::mem::take(&mut self.light)),
                 },
                 sensors::SensorData {
                     kind: sensors::SensorKind::Temperature,
-                    data: sensors::SensorDataDyn::F32(&self.temperature),
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.temperature)),
                 },
                 sensors::SensorData {
                     kind: sensors::SensorKind::BatteryVoltage,
-                    data: sensors::SensorDataDyn::F32(&self.battery_voltage),
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.battery_voltage)),
                 },
             ],
         }
     }
 }
 
-/// Per-block scaling parameters that are constant for the whole recording.
-/// Cached from the first valid data block and reused.
-#[derive(Debug, Clone, Copy)]
+/// Per-block format and scaling parameters. They usually stay the same for
+/// the whole recording, but may change between sectors of reconfigured or
+/// stitched files, so every sector is decoded with its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct BlockParameters {
+    /// Sample rate and range byte at offset 24.
+    rate_byte: u8,
     num_axes: u8,
     packed: bool,
     accel_scale: f32,
@@ -99,22 +127,137 @@ struct BlockParameters {
     fractional_format: bool,
 }
 
+impl BlockParameters {
+    /// AX6 sectors with 6 or 9 axes start each sample with the gyroscope.
+    fn has_gyro(&self) -> bool {
+        !self.packed && self.num_axes >= 6
+    }
+
+    /// AX6 sectors with 9 axes end each sample with the magnetometer.
+    fn has_magnetometer(&self) -> bool {
+        !self.packed && self.num_axes >= 9
+    }
+
+    /// Describe the run of sectors starting with this configuration
+    fn segment(&self, start_sector: usize, start_time: i64) -> sensors::Segment {
+        let (_, range_g) = decode_sample_rate(self.rate_byte);
+        sensors::Segment {
+            start_sector,
+            start_time,
+            sample_rate: 1e9 / self.nanos_per_sample as f64,
+            accelerometer_range: range_g,
+            gyroscope_range: self.has_gyro().then_some(self.gyro_scale * 32768.0),
+            axes: self.num_axes,
+        }
+    }
+}
+
+/// Optional channels of the `high_frequency` table. They are those of any
+/// valid sector in the file, so the columns neither depend on the first
+/// sector nor on the time window.
+#[derive(Debug, Clone, Copy, Default)]
+struct Columns {
+    gyro: bool,
+    magnetometer: bool,
+}
+
+impl Columns {
+    /// Scan the valid sectors for their channels, stopping once a 9-axis
+    /// sector shows that every channel is present.
+    fn scan<R: Read + Seek>(reader: &mut R, num_sectors: usize) -> Columns {
+        let mut columns = Columns::default();
+        let mut indices = 0..num_sectors;
+        while !columns.magnetometer
+            && let Some((index, sector)) = find_valid_sector(reader, indices.by_ref())
+        {
+            let location = FileLocation {
+                record_index: Some(index),
+                ..FileLocation::new()
+            };
+            if let Ok(params) = BlockParameters::parse(&sector, &location) {
+                columns.gyro |= params.has_gyro();
+                columns.magnetometer |= params.has_magnetometer();
+            }
+        }
+        columns
+    }
+}
+
+impl std::fmt::Display for BlockParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (rate_hz, range_g) = decode_sample_rate(self.rate_byte);
+        write!(
+            f,
+            "{} Hz, ±{} g, {} LSB/g",
+            rate_hz,
+            range_g,
+            1.0 / self.accel_scale
+        )?;
+        if self.packed {
+            write!(f, ", {} packed axes", self.num_axes)?;
+        } else {
+            write!(f, ", {} axes", self.num_axes)?;
+        }
+        if self.has_gyro() {
+            write!(f, ", ±{} dps", self.gyro_scale * 32768.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reader for Axivity AX3 and AX6 `.cwa` files
 #[derive(Default)]
 pub struct AxivityReader {
     high_frequency_data: HighFrequencyData,
     low_frequency_data: LowFrequencyData,
+    options: sensors::ReadOptions,
 }
 
 impl AxivityReader {
+    /// Reader with the default [`ReadOptions`](sensors::ReadOptions)
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Reader that applies `options`, such as the time window, sensors or
+    /// timestamp mode
+    pub fn with_options(options: sensors::ReadOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    fn emit_sensor_tables<S: FnMut(Vec<sensors::SensorTable>)>(
+        &mut self,
+        sensor_table_callback: &mut S,
+    ) {
+        let tables = [
+            self.low_frequency_data.take_sensor_table(),
+            self.high_frequency_data.take_sensor_table(),
+        ];
+        sensor_table_callback(
+            tables
+                .into_iter()
+                .filter_map(|table| table.select(self.options.sensors))
+                .collect(),
+        );
+    }
+}
+
+/// Identifiers from the header that every data sector should repeat
+struct HeaderIds {
+    device_id: u32,
+    session_id: u32,
+    /// AX3 devices only record the accelerometer, so their sectors need no
+    /// scan for other channels.
+    accelerometer_only: bool,
 }
 
 fn parse_header<M: FnMut(sensors::MetadataEntry)>(
     header: &[u8; HEADER_SIZE],
     mut metadata_callback: M,
-) -> Result<()> {
+) -> Result<HeaderIds> {
     if &header[0..2] != HEADER_MAGIC {
         return Err(ActfastError::Parse {
             format: FileFormat::AxivityCwa,
@@ -155,59 +298,59 @@ fn parse_header<M: FnMut(sensors::MetadataEntry)>(
     metadata_callback(sensors::MetadataEntry {
         category: "device",
         key: "hardware_type",
-        value: hardware_type,
+        value: MetadataValue::String(hardware_type),
     });
     metadata_callback(sensors::MetadataEntry {
         category: "device",
         key: "device_id",
-        value: &device_id.to_string(),
+        value: MetadataValue::String(&device_id.to_string()),
     });
     metadata_callback(sensors::MetadataEntry {
         category: "device",
         key: "session_id",
-        value: &session_id.to_string(),
+        value: MetadataValue::String(&session_id.to_string()),
     });
     metadata_callback(sensors::MetadataEntry {
         category: "device",
         key: "firmware_revision",
-        value: &firmware_revision.to_string(),
+        value: MetadataValue::Int(firmware_revision as i64),
     });
     metadata_callback(sensors::MetadataEntry {
         category: "device",
         key: "time_zone",
-        value: &time_zone.to_string(),
+        value: MetadataValue::Int(time_zone as i64),
     });
     metadata_callback(sensors::MetadataEntry {
         category: "configuration",
         key: "sample_rate_hz",
-        value: &rate_hz.to_string(),
+        value: MetadataValue::Int(rate_hz as i64),
     });
     metadata_callback(sensors::MetadataEntry {
         category: "configuration",
         key: "accelerometer_range_g",
-        value: &range_g.to_string(),
+        value: MetadataValue::Int(range_g as i64),
     });
 
-    // Logging start/end are packed timestamps; emit as raw values if decodable.
+    // Logging start/end are packed timestamps; emit only if decodable.
     if let Ok(ts) = decode_timestamp(logging_start, FileLocation::new()) {
         metadata_callback(sensors::MetadataEntry {
             category: "configuration",
             key: "logging_start",
-            value: &ts.to_rfc3339(),
+            value: MetadataValue::DateTime(ts),
         });
     }
     if let Ok(ts) = decode_timestamp(logging_end, FileLocation::new()) {
         metadata_callback(sensors::MetadataEntry {
             category: "configuration",
             key: "logging_end",
-            value: &ts.to_rfc3339(),
+            value: MetadataValue::DateTime(ts),
         });
     }
     if logging_capacity != 0 {
         metadata_callback(sensors::MetadataEntry {
             category: "configuration",
             key: "logging_capacity",
-            value: &logging_capacity.to_string(),
+            value: MetadataValue::Int(logging_capacity as i64),
         });
     }
 
@@ -224,12 +367,229 @@ fn parse_header<M: FnMut(sensors::MetadataEntry)>(
             metadata_callback(sensors::MetadataEntry {
                 category: "session",
                 key: "annotation",
-                value: trimmed,
+                value: MetadataValue::String(trimmed),
             });
+            for field in annotation::parse(trimmed).unwrap_or_default() {
+                metadata_callback(sensors::MetadataEntry {
+                    category: field.category,
+                    key: &field.key,
+                    value: if field.is_numeric() {
+                        MetadataValue::float_or_str(&field.value)
+                    } else {
+                        MetadataValue::String(&field.value)
+                    },
+                });
+            }
         }
     }
 
-    Ok(())
+    Ok(HeaderIds {
+        device_id,
+        session_id,
+        accelerometer_only: hardware_type == "AX3",
+    })
+}
+
+/// A run of consecutive sectors from another device or session, reported as
+/// one warning once it ends
+struct ForeignRun {
+    /// `"device"` or `"session"`
+    kind: &'static str,
+    id: u32,
+    expected: u32,
+    first: FileLocation,
+    last: FileLocation,
+    count: usize,
+}
+
+impl ForeignRun {
+    /// Add a sector from `id` (`None` if it is not foreign) to `run`, and
+    /// report the run if the sector ends it.
+    fn update(
+        run: &mut Option<ForeignRun>,
+        kind: &'static str,
+        id: Option<u32>,
+        expected: u32,
+        location: &FileLocation,
+        warnings: &mut Vec<String>,
+    ) {
+        if let (Some(run), Some(id)) = (run.as_mut(), id)
+            && run.id == id
+        {
+            run.last = location.clone();
+            run.count += 1;
+            return;
+        }
+        if let Some(ended) = run.take() {
+            warnings.push(ended.warning());
+        }
+        *run = id.map(|id| ForeignRun {
+            kind,
+            id,
+            expected,
+            first: location.clone(),
+            last: location.clone(),
+            count: 1,
+        });
+    }
+
+    fn warning(&self) -> String {
+        if self.count == 1 {
+            format!(
+                "sector from {} {} in a file of {} {} at {}",
+                self.kind, self.id, self.kind, self.expected, self.first
+            )
+        } else {
+            format!(
+                "{} sectors from {} {} in a file of {} {} at {} to {}",
+                self.count, self.kind, self.id, self.kind, self.expected, self.first, self.last
+            )
+        }
+    }
+}
+
+/// Checks that valid data sectors belong to the header's device and session
+/// and continue the sequence IDs of the previous valid sector. Sectors
+/// skipped as invalid count as present.
+struct SectorIdCheck {
+    device_id: u32,
+    session_id: u32,
+    /// Index and sequence ID of the last valid sector.
+    previous: Option<(usize, u32)>,
+    foreign_device: Option<ForeignRun>,
+    foreign_session: Option<ForeignRun>,
+}
+
+impl SectorIdCheck {
+    fn new(ids: &HeaderIds) -> Self {
+        Self {
+            device_id: ids.device_id,
+            session_id: ids.session_id,
+            previous: None,
+            foreign_device: None,
+            foreign_session: None,
+        }
+    }
+
+    fn check(
+        &mut self,
+        sector: &[u8; SECTOR_SIZE],
+        index: usize,
+        location: &FileLocation,
+        warnings: &mut Vec<String>,
+    ) {
+        let device_fractional = u16::from_le_bytes([sector[4], sector[5]]);
+        let session_id = u32::from_le_bytes([sector[6], sector[7], sector[8], sector[9]]);
+        let sequence_id = u32::from_le_bytes([sector[10], sector[11], sector[12], sector[13]]);
+
+        // Without the fractional flag, the field holds the low 15 bits of the
+        // device ID (0 if unknown).
+        let device_id = (device_fractional & 0x8000 == 0)
+            .then_some(device_fractional as u32)
+            .filter(|&id| id != 0 && id != self.device_id & 0x7FFF);
+        ForeignRun::update(
+            &mut self.foreign_device,
+            "device",
+            device_id,
+            self.device_id,
+            location,
+            warnings,
+        );
+        ForeignRun::update(
+            &mut self.foreign_session,
+            "session",
+            (session_id != self.session_id).then_some(session_id),
+            self.session_id,
+            location,
+            warnings,
+        );
+
+        let previous = self.previous.replace((index, sequence_id));
+        let Some((previous_index, previous_id)) = previous else {
+            return;
+        };
+        let expected = previous_id.wrapping_add((index - previous_index) as u32);
+        if sequence_id == expected {
+            return;
+        }
+        let warning = if sequence_id == previous_id {
+            format!("sequence ID {} repeated", sequence_id)
+        } else if sequence_id > expected {
+            let missing = sequence_id - expected;
+            format!(
+                "sequence ID jumps from {} to {} ({} sector{} missing)",
+                previous_id,
+                sequence_id,
+                missing,
+                if missing == 1 { "" } else { "s" }
+            )
+        } else {
+            format!(
+                "sequence ID {} out of order after {}",
+                sequence_id, previous_id
+            )
+        };
+        warnings.push(format!("{} at {}", warning, location));
+    }
+
+    /// Report the runs of foreign sectors still open at the end of the data
+    fn finish(&mut self, warnings: &mut Vec<String>) {
+        for run in [self.foreign_device.take(), self.foreign_session.take()] {
+            warnings.extend(run.map(|run| run.warning()));
+        }
+    }
+}
+
+/// Find the first sector that may contain samples at or after `start`.
+///
+/// Sectors have a fixed size, so this is a binary search over the block
+/// timestamps. A block timestamp is truncated to the second and belongs to a
+/// sample somewhere inside its sector, so the search aims one second early and
+/// then steps back one sector. Unreadable sectors end up on the "later" side,
+/// which at worst makes reading start earlier than necessary.
+fn find_start_sector<R: Read + Seek>(reader: &mut R, num_sectors: usize, start: i64) -> usize {
+    let target = start.saturating_sub(1_000_000_000);
+    let mut sector = [0u8; SECTOR_SIZE];
+    let (mut lo, mut hi) = (0usize, num_sectors);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let timestamp = reader
+            .seek(SeekFrom::Start((HEADER_SIZE + mid * SECTOR_SIZE) as u64))
+            .and_then(|_| reader.read_exact(&mut sector))
+            .ok()
+            .filter(|_| &sector[0..2] == DATA_MAGIC && check_sector_checksum(&sector))
+            .and_then(|_| {
+                let packed = u32::from_le_bytes([sector[14], sector[15], sector[16], sector[17]]);
+                decode_timestamp(packed, FileLocation::new()).ok()
+            })
+            .and_then(|ts| ts.timestamp_nanos_opt());
+        match timestamp {
+            Some(t) if t < target => lo = mid + 1,
+            _ => hi = mid,
+        }
+    }
+    lo.saturating_sub(1)
+}
+
+/// Find the first sector in `indices` with a valid magic and checksum.
+/// Unreadable sectors are skipped, like invalid ones.
+fn find_valid_sector<R: Read + Seek>(
+    reader: &mut R,
+    indices: impl Iterator<Item = usize>,
+) -> Option<(usize, [u8; SECTOR_SIZE])> {
+    let mut sector = [0u8; SECTOR_SIZE];
+    for index in indices {
+        let valid = reader
+            .seek(SeekFrom::Start((HEADER_SIZE + index * SECTOR_SIZE) as u64))
+            .and_then(|_| reader.read_exact(&mut sector))
+            .is_ok()
+            && &sector[0..2] == DATA_MAGIC
+            && check_sector_checksum(&sector);
+        if valid {
+            return Some((index, sector));
+        }
+    }
+    None
 }
 
 /// Validate a sector's 16-bit checksum: the sum (mod 2^16) of all 256 little-endian
@@ -247,11 +607,131 @@ fn check_sector_checksum(sector: &[u8; SECTOR_SIZE]) -> bool {
     sum == 0
 }
 
-impl AxivityReader {
-    fn parse_first_data_block_parameters(
+/// Number of samples stored in a data sector.
+fn sector_sample_count(sector: &[u8; SECTOR_SIZE]) -> usize {
+    u16::from_le_bytes([sector[28], sector[29]]) as usize
+}
+
+/// Sample timing of one data sector.
+#[derive(Debug, Clone, Copy)]
+struct SectorTiming {
+    /// Time of sample `shift`, the sample the block timestamp refers to.
+    origin_nanos: i64,
+    shift: i64,
+    /// The sample interval is `step_nanos / step_samples`, kept as a fraction
+    /// so interpolated intervals don't accumulate rounding errors.
+    step_nanos: i64,
+    step_samples: i64,
+}
+
+impl SectorTiming {
+    fn parse(
         sector: &[u8; SECTOR_SIZE],
+        params: &BlockParameters,
         location: &FileLocation,
-    ) -> Result<BlockParameters> {
+    ) -> Result<SectorTiming> {
+        let tsoffset = u16::from_le_bytes([sector[4], sector[5]]);
+        let timestamp_packed = u32::from_le_bytes([sector[14], sector[15], sector[16], sector[17]]);
+        let offset26 = i16::from_le_bytes([sector[26], sector[27]]);
+
+        let timestamp = decode_timestamp(timestamp_packed, location.clone())?;
+        let timestamp_nanos =
+            timestamp
+                .timestamp_nanos_opt()
+                .ok_or_else(|| ActfastError::InvalidDateTime {
+                    value: timestamp.to_string(),
+                    format: "timestamp out of nanosecond range",
+                    location: location.clone(),
+                })?;
+
+        // Block start time offset within the buffer:
+        //   shift = offset26 + (fractional * frequency) >> 16   (if fractional format)
+        // and the actual whole-second timestamp applies to sample[shift].
+        let mut shift = offset26 as i64;
+        let mut fractional_ns: i64 = 0;
+        if params.fractional_format {
+            let fractional = ((tsoffset & 0x7FFF) as u32) << 1;
+            // Whole-sample equivalent of the fractional second.
+            let freq = 1_000_000_000i64 / params.nanos_per_sample.max(1);
+            shift += ((fractional as i64) * freq) >> 16;
+            // Fractional offset in nanoseconds: fractional / 65536 of a second.
+            fractional_ns = ((fractional as i64) * 1_000_000_000) >> 16;
+        }
+        // sample[shift].time = timestamp_nanos + fractional_ns
+        // sample[i].time     = timestamp_nanos + fractional_ns + (i - shift) * nanos_per_sample
+        let block_origin_nanos = timestamp_nanos + fractional_ns;
+
+        Ok(SectorTiming {
+            origin_nanos: block_origin_nanos,
+            shift,
+            step_nanos: params.nanos_per_sample,
+            step_samples: 1,
+        })
+    }
+
+    /// Timing that spaces the `samples` of this sector evenly up to the start
+    /// of the `next` sector. `None` if that interval is more than 10% off the
+    /// nominal one, as across a gap in the recording.
+    fn interpolated(&self, samples: usize, next: &SectorTiming) -> Option<SectorTiming> {
+        let step_samples = samples as i64;
+        let step_nanos = next.sample_time(0) - self.sample_time(0);
+        let nominal = step_samples * self.step_nanos / self.step_samples;
+        (step_samples > 0 && (step_nanos - nominal).abs() <= nominal / 10)
+            .then(|| self.with_step(step_nanos, step_samples))
+    }
+
+    /// Timing with the same start, but the sample interval
+    /// `step_nanos / step_samples`.
+    fn with_step(&self, step_nanos: i64, step_samples: i64) -> SectorTiming {
+        SectorTiming {
+            origin_nanos: self.sample_time(0),
+            shift: 0,
+            step_nanos,
+            step_samples,
+        }
+    }
+
+    /// Time of sample `index` within the sector.
+    fn sample_time(&self, index: usize) -> i64 {
+        self.origin_nanos + (index as i64 - self.shift) * self.step_nanos / self.step_samples
+    }
+}
+
+/// Report the flags of a sector's events byte as events at the sector start,
+/// and the overflows, which lose samples, also as warnings.
+fn push_sector_events(sector: &DataSector, time: i64, result: &mut sensors::ReadResult) {
+    let events = sector.data[22];
+    for (flag, kind) in DATA_EVENTS {
+        if events & flag == 0 {
+            continue;
+        }
+        if matches!(kind, "fifo_overflow" | "buffer_overflow") {
+            result.warnings.push(format!(
+                "device reported a {} at {}",
+                kind.replace('_', " "),
+                sector.location
+            ));
+        }
+        result.events.push(sensors::Event {
+            time,
+            kind,
+            payload: Vec::new(),
+        });
+    }
+}
+
+/// A valid data sector, held back until the next one is read so its sample
+/// interval can be interpolated.
+struct DataSector {
+    data: [u8; SECTOR_SIZE],
+    index: usize,
+    params: BlockParameters,
+    timing: SectorTiming,
+    location: FileLocation,
+}
+
+impl BlockParameters {
+    fn parse(sector: &[u8; SECTOR_SIZE], location: &FileLocation) -> Result<BlockParameters> {
         let offset18 = u16::from_le_bytes([sector[18], sector[19]]);
         let offset25 = sector[25];
         let sample_rate_byte = sector[24];
@@ -266,6 +746,19 @@ impl AxivityReader {
                 location: location.clone(),
             });
         }
+        let valid_axes = if packed {
+            num_axes == 3
+        } else {
+            matches!(num_axes, 3 | 6 | 9)
+        };
+        if !valid_axes {
+            return Err(ActfastError::InvalidField {
+                field: "numAxesBPS",
+                value: format!("0x{:02x}", offset25),
+                expected: "3 packed axes or 3, 6 or 9 unpacked axes",
+                location: location.clone(),
+            });
+        }
 
         let accel_scale_code = (offset18 >> 13) & 0x07;
         // accel_scale = 1 / 2^(8 + code), so raw_int / 256 for code=0
@@ -280,6 +773,7 @@ impl AxivityReader {
         let fractional_format = (tsoffset & 0x8000) != 0;
 
         Ok(BlockParameters {
+            rate_byte: sample_rate_byte,
             num_axes,
             packed,
             accel_scale,
@@ -288,63 +782,53 @@ impl AxivityReader {
             fractional_format,
         })
     }
+}
 
+impl AxivityReader {
+    /// Decode a data sector with its own `params` into the table `columns`.
+    /// Channels a sector lacks are filled with NaN.
     fn parse_data_sector(
         &mut self,
-        sector: &[u8; SECTOR_SIZE],
-        params: &BlockParameters,
-        location: &FileLocation,
-    ) -> Result<()> {
-        let tsoffset = u16::from_le_bytes([sector[4], sector[5]]);
-        let timestamp_packed =
-            u32::from_le_bytes([sector[14], sector[15], sector[16], sector[17]]);
+        data_sector: &DataSector,
+        timing: &SectorTiming,
+        columns: &Columns,
+    ) -> Result<i64> {
+        let sector = &data_sector.data;
+        let params = &data_sector.params;
+        let location = &data_sector.location;
         let offset18 = u16::from_le_bytes([sector[18], sector[19]]);
         let light = offset18 & 0x03FF;
         let temperature_raw = u16::from_le_bytes([sector[20], sector[21]]) & 0x03FF;
         let battery_byte = sector[23];
-        let offset26 = i16::from_le_bytes([sector[26], sector[27]]);
-        let sample_count = u16::from_le_bytes([sector[28], sector[29]]) as usize;
-
-        let timestamp = decode_timestamp(timestamp_packed, location.clone())?;
-        let timestamp_nanos =
-            timestamp
-                .timestamp_nanos_opt()
-                .ok_or_else(|| ActfastError::InvalidDateTime {
-                    value: timestamp.to_string(),
-                    format: "timestamp out of nanosecond range",
-                    location: location.clone(),
-                })?;
-
-        // Block start time offset within the buffer:
-        //   shift = offset26 + (fractional * frequency) >> 16   (if fractional format)
-        // and the actual whole-second timestamp applies to sample[shift].
-        let mut shift = offset26 as i64;
-        let mut fractional_ns: i64 = 0;
-        if params.fractional_format {
-            let fractional = ((tsoffset & 0x7FFF) as u32) << 1;
-            // Whole-sample equivalent of the fractional second.
-            let freq = 1_000_000_000i64 / params.nanos_per_sample.max(1);
-            shift += ((fractional as i64) * freq) >> 16;
-            // Fractional offset in nanoseconds: fractional / 65536 of a second.
-            fractional_ns = ((fractional as i64) * 1_000_000_000) >> 16;
-        }
-        // sample[shift].time = timestamp_nanos + fractional_ns
-        // sample[i].time     = timestamp_nanos + fractional_ns + (i - shift) * nanos_per_sample
-        let block_origin_nanos = timestamp_nanos + fractional_ns;
+        let sample_count = sector_sample_count(sector);
 
         // Battery: voltage = 3.0 * (byte / 256 + 1)
         let battery_voltage = 3.0 * (battery_byte as f32 / 256.0 + 1.0);
         // Temperature: °C = (raw & 0x3FF) * 75 / 256 - 50
         let temperature_c = (temperature_raw as f32) * 75.0 / 256.0 - 50.0;
 
-        self.low_frequency_data
-            .time
-            .push(block_origin_nanos - shift * params.nanos_per_sample);
-        self.low_frequency_data.light.push(light);
-        self.low_frequency_data.temperature.push(temperature_c);
-        self.low_frequency_data
-            .battery_voltage
-            .push(battery_voltage);
+        let selected = self.options.sensors;
+        let sector_start_nanos = timing.sample_time(0);
+        if self.options.in_window(sector_start_nanos)
+            && selected.contains_any(&LowFrequencyData::SENSORS)
+        {
+            self.low_frequency_data.time.push(sector_start_nanos);
+            if selected.contains(sensors::SensorKind::Light) {
+                self.low_frequency_data.light.push(light);
+            }
+            if selected.contains(sensors::SensorKind::Temperature) {
+                self.low_frequency_data.temperature.push(temperature_c);
+            }
+            if selected.contains(sensors::SensorKind::BatteryVoltage) {
+                self.low_frequency_data
+                    .battery_voltage
+                    .push(battery_voltage);
+            }
+        }
+
+        if !selected.contains_any(&HighFrequencyData::SENSORS) {
+            return Ok(sector_start_nanos);
+        }
 
         // Sample data spans bytes 30..510 (480 bytes), with checksum at 510-511.
         let sample_data = &sector[30..510];
@@ -364,12 +848,24 @@ impl AxivityReader {
         let max_samples = sample_data.len() / bytes_per_sample;
         let actual_samples = sample_count.min(max_samples);
 
-        let has_gyro = !params.packed && params.num_axes >= 6;
+        let has_gyro = params.has_gyro();
+        let want_accel = selected.contains(sensors::SensorKind::Accelerometer);
+        let want_gyro = columns.gyro && selected.contains(sensors::SensorKind::Gyroscope);
+        let want_magnetometer =
+            columns.magnetometer && selected.contains(sensors::SensorKind::MagnetometerCounts);
 
         for i in 0..actual_samples {
             let off = i * bytes_per_sample;
             let buf = &sample_data[off..off + bytes_per_sample];
 
+            // AX6 9-axis layout: mx, my, mz (int16) after gyro and accel.
+            let mxyz = params.has_magnetometer().then(|| {
+                (
+                    i16::from_le_bytes([buf[12], buf[13]]),
+                    i16::from_le_bytes([buf[14], buf[15]]),
+                    i16::from_le_bytes([buf[16], buf[17]]),
+                )
+            });
             let (ax, ay, az, gxyz) = if params.packed {
                 let word = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
                 let (x, y, z) = decode_packed_sample(word);
@@ -390,39 +886,82 @@ impl AxivityReader {
                 (x, y, z, None)
             };
 
-            let sample_time =
-                block_origin_nanos + (i as i64 - shift) * params.nanos_per_sample;
+            let sample_time = timing.sample_time(i);
+            if !self.options.in_window(sample_time) {
+                continue;
+            }
             self.high_frequency_data.time.push(sample_time);
-            self.high_frequency_data
-                .acceleration
-                .push(ax as f32 * params.accel_scale);
-            self.high_frequency_data
-                .acceleration
-                .push(ay as f32 * params.accel_scale);
-            self.high_frequency_data
-                .acceleration
-                .push(az as f32 * params.accel_scale);
-
-            if let Some((gx, gy, gz)) = gxyz {
+            if want_accel {
                 self.high_frequency_data
-                    .gyroscope
-                    .push(gx as f32 * params.gyro_scale);
+                    .acceleration
+                    .push(ax as f32 * params.accel_scale);
                 self.high_frequency_data
-                    .gyroscope
-                    .push(gy as f32 * params.gyro_scale);
+                    .acceleration
+                    .push(ay as f32 * params.accel_scale);
                 self.high_frequency_data
-                    .gyroscope
-                    .push(gz as f32 * params.gyro_scale);
+                    .acceleration
+                    .push(az as f32 * params.accel_scale);
+            }
+
+            if want_gyro {
+                self.high_frequency_data.gyroscope.extend(gxyz.map_or(
+                    [f32::NAN; 3],
+                    |(gx, gy, gz)| {
+                        [
+                            gx as f32 * params.gyro_scale,
+                            gy as f32 * params.gyro_scale,
+                            gz as f32 * params.gyro_scale,
+                        ]
+                    },
+                ));
+            }
+
+            if want_magnetometer {
+                // No magnetometer scale is documented for the CWA format, so
+                // the readings are kept in sensor counts
+                self.high_frequency_data.magnetometer.extend(
+                    mxyz.map_or([f32::NAN; 3], |(mx, my, mz)| {
+                        [mx as f32, my as f32, mz as f32]
+                    }),
+                );
             }
         }
 
-        Ok(())
+        Ok(sector_start_nanos)
     }
 }
 
-impl<'a> sensors::SensorsFormatReader<'a> for AxivityReader {
-    fn read<R: Read + std::io::Seek, M, S>(
-        &'a mut self,
+impl AxivityReader {
+    /// Decode a held back sector. Returns `true` if it starts after the time
+    /// window, so the rest of the file can be skipped.
+    fn decode_sector(
+        &mut self,
+        sector: &DataSector,
+        timing: &SectorTiming,
+        columns: &Columns,
+        result: &mut sensors::ReadResult,
+        lenient: bool,
+    ) -> Result<bool> {
+        match self.parse_data_sector(sector, timing, columns) {
+            // Sectors are time-ordered, the rest of the file is past the window.
+            Ok(sector_start) => {
+                if self.options.in_window(sector_start) {
+                    push_sector_events(sector, sector_start, result);
+                }
+                Ok(self.options.after_window(sector_start))
+            }
+            Err(e) if lenient => {
+                result.warnings.push(e.to_string());
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl sensors::SensorsFormatReader for AxivityReader {
+    fn read<R: Read + Seek, M, S>(
+        &mut self,
         mut reader: R,
         mut metadata_callback: M,
         mut sensor_table_callback: S,
@@ -430,7 +969,7 @@ impl<'a> sensors::SensorsFormatReader<'a> for AxivityReader {
     ) -> Result<sensors::ReadResult>
     where
         M: FnMut(sensors::MetadataEntry),
-        S: FnMut(sensors::SensorTable<'a>),
+        S: FnMut(Vec<sensors::SensorTable>),
     {
         let mut result = sensors::ReadResult::new();
 
@@ -442,23 +981,54 @@ impl<'a> sensors::SensorsFormatReader<'a> for AxivityReader {
                 source: e,
                 context: "reading CWA header".to_string(),
             })?;
-        parse_header(&header, &mut metadata_callback)?;
+        let header_ids = parse_header(&header, &mut metadata_callback)?;
 
         // Pre-reserve memory based on file size, assuming ~120 samples / sector
         // (worst-case 480 for AX3 unpacked, but allocations are amortised so the
         // overshoot doesn't matter for correctness).
         let total_len = reader
-            .seek(std::io::SeekFrom::End(0))
-            .and_then(|n| reader.seek(std::io::SeekFrom::Start(HEADER_SIZE as u64)).map(|_| n))
+            .seek(SeekFrom::End(0))
+            .and_then(|n| reader.seek(SeekFrom::Start(HEADER_SIZE as u64)).map(|_| n))
             .unwrap_or(0);
         let estimated_sectors = (total_len as usize).saturating_sub(HEADER_SIZE) / SECTOR_SIZE;
 
+        let want_columns = self.options.sensors.contains_any(&[
+            sensors::SensorKind::Gyroscope,
+            sensors::SensorKind::MagnetometerCounts,
+        ]);
+        let columns = if want_columns && !header_ids.accelerometer_only {
+            Columns::scan(&mut reader, estimated_sectors)
+        } else {
+            Columns::default()
+        };
+
+        // Jump straight to the start of the requested window.
+        let mut sector_index = match self.options.start {
+            Some(start) => find_start_sector(&mut reader, estimated_sectors, start),
+            None => 0,
+        };
+        reader
+            .seek(SeekFrom::Start(
+                (HEADER_SIZE + sector_index * SECTOR_SIZE) as u64,
+            ))
+            .map_err(|e| ActfastError::Io {
+                source: e,
+                context: format!("seeking to data sector {}", sector_index),
+            })?;
+
         // --- Data sectors ---
         let mut sector = [0u8; SECTOR_SIZE];
-        let mut sector_index: usize = 0;
-        let mut byte_offset: u64 = HEADER_SIZE as u64;
-        let mut params: Option<BlockParameters> = None;
+        let mut byte_offset: u64 = (HEADER_SIZE + sector_index * SECTOR_SIZE) as u64;
+        // Sectors whose configuration differs from the previous one start a
+        // new segment.
+        let mut previous: Option<BlockParameters> = None;
         let mut data_reserved = false;
+        let mut pending: Option<DataSector> = None;
+        let mut sector_ids = SectorIdCheck::new(&header_ids);
+        // Interval of the last interpolated sector, reused for the final one.
+        let mut last_step: Option<(i64, i64)> = None;
+        // Nanoseconds and samples between the starts of consecutive sectors.
+        let (mut observed_nanos, mut observed_samples) = (0i64, 0i64);
 
         loop {
             match reader.read_exact(&mut sector) {
@@ -499,6 +1069,7 @@ impl<'a> sensors::SensorsFormatReader<'a> for AxivityReader {
             }
 
             if !check_sector_checksum(&sector) {
+                result.checksum_mismatches += 1;
                 let warning = format!(
                     "sector {} (byte offset {}) failed checksum",
                     sector_index, byte_offset
@@ -517,40 +1088,189 @@ impl<'a> sensors::SensorsFormatReader<'a> for AxivityReader {
                 }
             }
 
-            // First valid block sets the format parameters.
-            if params.is_none() {
-                params = Some(Self::parse_first_data_block_parameters(&sector, &location)?);
-            }
-            let p = params.unwrap();
-
+            let p = match BlockParameters::parse(&sector, &location) {
+                Ok(p) => p,
+                Err(e) if lenient => {
+                    result.warnings.push(e.to_string());
+                    sector_index += 1;
+                    byte_offset += SECTOR_SIZE as u64;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             if !data_reserved {
-                let est_samples_per_sector = if p.packed { 120 } else { 480 / (p.num_axes as usize).max(1) / 2 };
+                let est_samples_per_sector = if p.packed {
+                    120
+                } else {
+                    480 / (p.num_axes as usize).max(1) / 2
+                };
+                let est_samples = self
+                    .options
+                    .reserve_limit(estimated_sectors * est_samples_per_sector);
                 self.high_frequency_data
-                    .reserve(estimated_sectors * est_samples_per_sector, p.num_axes >= 6);
-                self.low_frequency_data.reserve(estimated_sectors);
+                    .reserve(est_samples, &columns, self.options.sensors);
+                self.low_frequency_data
+                    .reserve(est_samples.div_ceil(est_samples_per_sector.max(1)));
                 data_reserved = true;
             }
 
-            match self.parse_data_sector(&sector, &p, &location) {
-                Ok(()) => {}
-                Err(e) => {
-                    if lenient {
-                        result.warnings.push(e.to_string());
-                    } else {
-                        return Err(e);
-                    }
+            sector_ids.check(&sector, sector_index, &location, &mut result.warnings);
+
+            let timing = match SectorTiming::parse(&sector, &p, &location) {
+                Ok(timing) => timing,
+                Err(e) if lenient => {
+                    result.warnings.push(e.to_string());
+                    sector_index += 1;
+                    byte_offset += SECTOR_SIZE as u64;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if previous != Some(p) {
+                if let Some(previous) = previous {
+                    result.warnings.push(format!(
+                        "sector {} (byte offset {}) changes the configuration from {} to {}",
+                        sector_index, byte_offset, previous, p
+                    ));
+                }
+                result
+                    .segments
+                    .push(p.segment(sector_index, timing.sample_time(0)));
+            }
+            previous = Some(p);
+            let current = DataSector {
+                data: sector,
+                index: sector_index,
+                params: p,
+                timing,
+                location,
+            };
+
+            if let Some(held) = pending.take() {
+                let interpolated = (current.index == held.index + 1
+                    && current.params == held.params)
+                    .then(|| {
+                        held.timing
+                            .interpolated(sector_sample_count(&held.data), &current.timing)
+                    })
+                    .flatten();
+                last_step = interpolated.map(|t| (t.step_nanos, t.step_samples));
+                if let Some((step_nanos, step_samples)) = last_step {
+                    observed_nanos += step_nanos;
+                    observed_samples += step_samples;
+                }
+                let timing = match (self.options.timestamps, interpolated) {
+                    (sensors::Timestamps::Interpolated, Some(timing)) => timing,
+                    _ => held.timing,
+                };
+                if self.decode_sector(&held, &timing, &columns, &mut result, lenient)? {
+                    break;
                 }
             }
+            pending = Some(current);
 
             sector_index += 1;
             byte_offset += SECTOR_SIZE as u64;
+
+            let buffered = self
+                .high_frequency_data
+                .time
+                .len()
+                .max(self.low_frequency_data.time.len());
+            if self.options.chunk_full(buffered) {
+                self.emit_sensor_tables(&mut sensor_table_callback);
+                if self.options.cancelled() {
+                    sector_ids.finish(&mut result.warnings);
+                    return Ok(result);
+                }
+                data_reserved = false;
+            }
         }
+        sector_ids.finish(&mut result.warnings);
 
-        sensor_table_callback(self.low_frequency_data.sensor_table());
-        sensor_table_callback(self.high_frequency_data.sensor_table());
+        if let Some(held) = pending {
+            let timing = match (self.options.timestamps, last_step) {
+                (sensors::Timestamps::Interpolated, Some((step_nanos, step_samples))) => {
+                    held.timing.with_step(step_nanos, step_samples)
+                }
+                _ => held.timing,
+            };
+            self.decode_sector(&held, &timing, &columns, &mut result, lenient)?;
+        }
+        self.emit_sensor_tables(&mut sensor_table_callback);
 
+        result.effective_sample_rate =
+            (observed_nanos > 0).then(|| observed_samples as f64 * 1e9 / observed_nanos as f64);
         Ok(result)
     }
+
+    fn info<R: Read + Seek, M>(
+        &mut self,
+        mut reader: R,
+        mut metadata_callback: M,
+    ) -> Result<sensors::RecordingInfo>
+    where
+        M: FnMut(sensors::MetadataEntry),
+    {
+        let mut header = [0u8; HEADER_SIZE];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| ActfastError::Io {
+                source: e,
+                context: "reading CWA header".to_string(),
+            })?;
+        let header_ids = parse_header(&header, &mut metadata_callback)?;
+
+        let total_len = reader
+            .seek(SeekFrom::End(0))
+            .map_err(|e| ActfastError::Io {
+                source: e,
+                context: "seeking to the end of the CWA file".to_string(),
+            })?;
+        let num_sectors = (total_len as usize).saturating_sub(HEADER_SIZE) / SECTOR_SIZE;
+
+        // The first and last valid data sectors bound the recording.
+        let mut info = sensors::RecordingInfo::default();
+        let Some((first_index, first)) = find_valid_sector(&mut reader, 0..num_sectors) else {
+            return Ok(info);
+        };
+        let (last_index, last) = find_valid_sector(&mut reader, (first_index..num_sectors).rev())
+            .unwrap_or((first_index, first));
+
+        let location = |index: usize| FileLocation {
+            byte_offset: Some((HEADER_SIZE + index * SECTOR_SIZE) as u64),
+            record_index: Some(index),
+            sample_index: None,
+            line_number: None,
+        };
+        let params = BlockParameters::parse(&first, &location(first_index))?;
+        let last_params = BlockParameters::parse(&last, &location(last_index))?;
+        let first_timing = SectorTiming::parse(&first, &params, &location(first_index))?;
+        let last_timing = SectorTiming::parse(&last, &last_params, &location(last_index))?;
+
+        info.first_timestamp = Some(first_timing.sample_time(0));
+        info.last_timestamp =
+            Some(last_timing.sample_time(sector_sample_count(&last).saturating_sub(1)));
+        info.sample_rate = Some(1e9 / params.nanos_per_sample as f64);
+        info.estimated_samples =
+            Some((last_index - first_index + 1) as u64 * sector_sample_count(&first) as u64);
+
+        let columns = if header_ids.accelerometer_only {
+            Columns::default()
+        } else {
+            Columns::scan(&mut reader, num_sectors)
+        };
+        info.sensors.push(sensors::SensorKind::Accelerometer);
+        if columns.gyro {
+            info.sensors.push(sensors::SensorKind::Gyroscope);
+        }
+        if columns.magnetometer {
+            info.sensors.push(sensors::SensorKind::MagnetometerCounts);
+        }
+        info.sensors.extend(LowFrequencyData::SENSORS);
+
+        Ok(info)
+    }
 }
 
 #[cfg(test)]
@@ -576,11 +1296,13 @@ mod tests {
             |entry| {
                 metadata.insert(
                     (entry.category.to_owned(), entry.key.to_owned()),
-                    entry.value.to_owned(),
+                    entry.value.into_owned(),
                 );
             },
-            |table| {
-                sensor_table.insert(table.name, table);
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
             },
             false,
         );
@@ -588,15 +1310,18 @@ mod tests {
         assert!(result.unwrap().warnings.is_empty());
 
         // Header metadata
-        assert_eq!(metadata[&("device".into(), "hardware_type".into())], "AX3");
+        assert_eq!(
+            metadata[&("device".into(), "hardware_type".into())],
+            MetadataValue::String("AX3".into())
+        );
         assert_eq!(
             metadata[&("configuration".into(), "sample_rate_hz".into())],
-            "100"
+            MetadataValue::Int(100)
         );
         // First sector's accelScaleCode=0 means 8g range encoded in sample rate byte
         assert_eq!(
             metadata[&("configuration".into(), "accelerometer_range_g".into())],
-            "8"
+            MetadataValue::Int(8)
         );
 
         // Sensor tables
@@ -662,18 +1387,23 @@ mod tests {
             |entry| {
                 metadata.insert(
                     (entry.category.to_owned(), entry.key.to_owned()),
-                    entry.value.to_owned(),
+                    entry.value.into_owned(),
                 );
             },
-            |table| {
-                sensor_table.insert(table.name, table);
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
             },
             false,
         );
         assert!(result.is_ok(), "read failed: {:?}", result.err());
         assert!(result.unwrap().warnings.is_empty());
 
-        assert_eq!(metadata[&("device".into(), "hardware_type".into())], "AX6");
+        assert_eq!(
+            metadata[&("device".into(), "hardware_type".into())],
+            MetadataValue::String("AX6".into())
+        );
 
         let low = sensor_table.get("low_frequency").unwrap();
         let high = sensor_table.get("high_frequency").unwrap();
@@ -735,8 +1465,10 @@ mod tests {
         let result = reader.read(
             Cursor::new(&data[..]),
             |_| {},
-            |table| {
-                sensor_table.insert(table.name, table);
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
             },
             true,
         );
@@ -747,6 +1479,7 @@ mod tests {
             "expected 4 corrupt-block warnings, got: {:?}",
             read_result.warnings
         );
+        assert_eq!(read_result.checksum_mismatches, 4);
 
         let low = sensor_table.get("low_frequency").unwrap();
         let high = sensor_table.get("high_frequency").unwrap();
@@ -766,6 +1499,497 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_axivity_reader_window() {
+        let mut whole = HashMap::new();
+        AxivityReader::new()
+            .read(
+                Cursor::new(AX3_BYTES),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        whole.insert(table.name, table);
+                    }
+                },
+                false,
+            )
+            .unwrap();
+        let all_times = &whole["high_frequency"].datetime;
+        let (start, end) = (all_times[1000], all_times[2500]);
+
+        // Sector 0 is corrupt: a strict read only succeeds if it is skipped by seeking.
+        let data = corrupt_ax3_bytes(&[0]);
+        let mut reader = AxivityReader::with_options(sensors::ReadOptions {
+            start: Some(start),
+            end: Some(end),
+            ..sensors::ReadOptions::default()
+        });
+        let mut sensor_table = HashMap::new();
+        reader
+            .read(
+                Cursor::new(&data[..]),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        sensor_table.insert(table.name, table);
+                    }
+                },
+                false,
+            )
+            .expect("window read should skip the corrupt first sector");
+
+        let high = &sensor_table["high_frequency"];
+        assert_eq!(high.datetime, all_times[1000..2500]);
+        if let sensors::SensorDataDyn::F32(accel) = &high.data[0].data {
+            assert_eq!(accel.len(), 1500 * 3);
+        } else {
+            panic!("expected F32 acceleration data");
+        }
+        let low = &sensor_table["low_frequency"];
+        assert!(!low.datetime.is_empty());
+        assert!(low.datetime.iter().all(|&t| t >= start && t < end));
+    }
+
+    /// Synthesise a 9-axis CWA from the AX6 bytes: mark every sector as 9 axes
+    /// with 26 samples, give the first sample a magnetometer reading and fix up
+    /// the checksum.
+    fn ax6_to_9_axis_bytes() -> Vec<u8> {
+        let mut data = AX6_BYTES.to_vec();
+        for sector in data[HEADER_SIZE..].chunks_exact_mut(SECTOR_SIZE) {
+            sector[25] = 0x92;
+            sector[28..30].copy_from_slice(&26u16.to_le_bytes());
+            for (i, value) in [16i16, -32, 800].into_iter().enumerate() {
+                sector[42 + i * 2..44 + i * 2].copy_from_slice(&value.to_le_bytes());
+            }
+            fix_sector_checksum(sector);
+        }
+        data
+    }
+
+    /// Rewrite the last word of a sector so its checksum is valid again
+    fn fix_sector_checksum(sector: &mut [u8]) {
+        let sum = sector[..510].chunks_exact(2).fold(0u16, |sum, w| {
+            sum.wrapping_add(u16::from_le_bytes([w[0], w[1]]))
+        });
+        sector[510..512].copy_from_slice(&sum.wrapping_neg().to_le_bytes());
+    }
+
+    #[test]
+    fn test_axivity_reader_magnetometer() {
+        let data = ax6_to_9_axis_bytes();
+        let mut sensor_table = HashMap::new();
+        let result = AxivityReader::new()
+            .read(
+                Cursor::new(&data),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        sensor_table.insert(table.name, table);
+                    }
+                },
+                false,
+            )
+            .unwrap();
+        assert!(result.warnings.is_empty());
+
+        let high = &sensor_table["high_frequency"];
+        assert_eq!(high.datetime.len(), 30 * 26);
+        let kinds: Vec<_> = high.data.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                sensors::SensorKind::Accelerometer,
+                sensors::SensorKind::Gyroscope,
+                sensors::SensorKind::MagnetometerCounts,
+            ]
+        );
+        let sensors::SensorDataDyn::F32(mag) = &high.data[2].data else {
+            panic!("expected F32 magnetometer counts");
+        };
+        assert_eq!(mag.len(), 30 * 26 * 3);
+        assert_eq!(mag[..3], [16.0, -32.0, 800.0]);
+
+        let info = AxivityReader::new()
+            .info(Cursor::new(&data), |_| {})
+            .unwrap();
+        assert!(
+            info.sensors
+                .contains(&sensors::SensorKind::MagnetometerCounts)
+        );
+    }
+
+    #[test]
+    fn test_axivity_reader_configuration_change() {
+        // Reconfigure the second half of the AX6 file to 3 axes at 1024 LSB/g.
+        let mut data = AX6_BYTES.to_vec();
+        for sector in data[HEADER_SIZE + 15 * SECTOR_SIZE..].chunks_exact_mut(SECTOR_SIZE) {
+            sector[25] = 0x32;
+            sector[19] = (sector[19] & 0x1F) | (2 << 5);
+            fix_sector_checksum(sector);
+        }
+        let sector_15 = HEADER_SIZE + 15 * SECTOR_SIZE;
+        let raw_x = i16::from_le_bytes([data[sector_15 + 30], data[sector_15 + 31]]);
+
+        let mut sensor_table = HashMap::new();
+        let result = AxivityReader::new()
+            .read(
+                Cursor::new(data),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        sensor_table.insert(table.name, table);
+                    }
+                },
+                false,
+            )
+            .unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        assert!(
+            result.warnings[0].contains("sector 15")
+                && result.warnings[0].ends_with(
+                    "from 100 Hz, ±16 g, 2048 LSB/g, 6 axes, ±250 dps \
+                     to 100 Hz, ±16 g, 1024 LSB/g, 3 axes"
+                ),
+            "{}",
+            result.warnings[0]
+        );
+
+        let high = &sensor_table["high_frequency"];
+        assert_eq!(high.datetime.len(), 30 * 40);
+        let (sensors::SensorDataDyn::F32(accel), sensors::SensorDataDyn::F32(gyro)) =
+            (&high.data[0].data, &high.data[1].data)
+        else {
+            panic!("expected F32 acceleration and gyroscope data");
+        };
+        assert_eq!(gyro.len(), accel.len());
+        assert!(gyro[..15 * 40 * 3].iter().all(|g| !g.is_nan()));
+        assert!(gyro[15 * 40 * 3..].iter().all(|g| g.is_nan()));
+        assert_approx_eq!(accel[15 * 40 * 3], raw_x as f32 / 1024.0, 1e-6);
+
+        assert_eq!(
+            result.segments,
+            [
+                sensors::Segment {
+                    start_sector: 0,
+                    start_time: high.datetime[0],
+                    sample_rate: 100.0,
+                    accelerometer_range: 16,
+                    gyroscope_range: Some(250.0),
+                    axes: 6,
+                },
+                sensors::Segment {
+                    start_sector: 15,
+                    start_time: high.datetime[15 * 40],
+                    sample_rate: 100.0,
+                    accelerometer_range: 16,
+                    gyroscope_range: None,
+                    axes: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_axivity_reader_columns_from_all_sectors() {
+        // Reconfigure the first half of the AX6 file to 3 axes, so the
+        // gyroscope only appears from sector 15 on.
+        let mut data = AX6_BYTES.to_vec();
+        for sector in
+            data[HEADER_SIZE..HEADER_SIZE + 15 * SECTOR_SIZE].chunks_exact_mut(SECTOR_SIZE)
+        {
+            sector[25] = 0x32;
+            fix_sector_checksum(sector);
+        }
+
+        let read = |options: sensors::ReadOptions| {
+            let mut high = None;
+            let result = AxivityReader::with_options(options)
+                .read(
+                    Cursor::new(&data),
+                    |_| {},
+                    |tables| {
+                        high = tables.into_iter().find(|t| t.name == "high_frequency");
+                    },
+                    false,
+                )
+                .unwrap();
+            (high.unwrap(), result)
+        };
+
+        let (high, result) = read(sensors::ReadOptions::default());
+        let kinds: Vec<_> = high.data.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                sensors::SensorKind::Accelerometer,
+                sensors::SensorKind::Gyroscope
+            ]
+        );
+        let sensors::SensorDataDyn::F32(gyro) = &high.data[1].data else {
+            panic!("expected F32 gyroscope data");
+        };
+        assert!(gyro[..15 * 40 * 3].iter().all(|g| g.is_nan()));
+        assert!(gyro[15 * 40 * 3..].iter().all(|g| !g.is_nan()));
+        let axes: Vec<_> = result
+            .segments
+            .iter()
+            .map(|s| (s.start_sector, s.axes))
+            .collect();
+        assert_eq!(axes, [(0, 3), (15, 6)]);
+
+        // A window inside the 3-axis half keeps the same columns.
+        let (windowed, result) = read(sensors::ReadOptions {
+            start: Some(high.datetime[5 * 40]),
+            end: Some(high.datetime[10 * 40]),
+            ..Default::default()
+        });
+        assert_eq!(windowed.datetime.len(), 5 * 40);
+        let kinds: Vec<_> = windowed.data.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                sensors::SensorKind::Accelerometer,
+                sensors::SensorKind::Gyroscope
+            ]
+        );
+        assert!(result.segments.iter().all(|s| s.axes == 3));
+
+        let info = AxivityReader::new()
+            .info(Cursor::new(&data), |_| {})
+            .unwrap();
+        assert!(info.sensors.contains(&sensors::SensorKind::Gyroscope));
+    }
+
+    #[test]
+    fn test_axivity_reader_unknown_axes() {
+        for num_axes_bps in [0x42, 0x60] {
+            let mut data = AX6_BYTES.to_vec();
+            data[HEADER_SIZE + 25] = num_axes_bps;
+            fix_sector_checksum(&mut data[HEADER_SIZE..HEADER_SIZE + SECTOR_SIZE]);
+            let result = AxivityReader::new().read(Cursor::new(data), |_| {}, |_| {}, false);
+            assert!(matches!(
+                result.unwrap_err(),
+                ActfastError::InvalidField {
+                    field: "numAxesBPS",
+                    ..
+                }
+            ));
+        }
+    }
+
+    fn read_high_frequency_times(
+        data: &[u8],
+        timestamps: sensors::Timestamps,
+    ) -> (Vec<i64>, sensors::ReadResult) {
+        let mut datetime = Vec::new();
+        let result = AxivityReader::with_options(sensors::ReadOptions {
+            timestamps,
+            ..sensors::ReadOptions::default()
+        })
+        .read(
+            Cursor::new(data),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    if table.name == "high_frequency" {
+                        datetime = table.datetime;
+                    }
+                }
+            },
+            false,
+        )
+        .unwrap();
+        (datetime, result)
+    }
+
+    #[test]
+    fn test_axivity_reader_interpolated_timestamps() {
+        for data in [AX3_BYTES, AX6_BYTES] {
+            let (block, block_result) = read_high_frequency_times(data, sensors::Timestamps::Block);
+            let (interpolated, result) =
+                read_high_frequency_times(data, sensors::Timestamps::Interpolated);
+
+            let rate = result.effective_sample_rate.unwrap();
+            assert_eq!(block_result.effective_sample_rate, Some(rate));
+            assert!((rate - 100.0).abs() < 2.0, "effective rate {}", rate);
+
+            // Same samples, starting at the same time, but evenly spaced
+            // across sector boundaries.
+            assert_eq!(interpolated.len(), block.len());
+            assert_eq!(interpolated[0], block[0]);
+            let interval = 1e9 / rate;
+            for pair in interpolated.windows(2) {
+                let step = (pair[1] - pair[0]) as f64;
+                assert!((step - interval).abs() < interval * 0.03, "step {}", step);
+            }
+        }
+    }
+
+    #[test]
+    fn test_axivity_reader_sensor_selection() {
+        let mut reader = AxivityReader::with_options(sensors::ReadOptions {
+            sensors: [sensors::SensorKind::Accelerometer].into_iter().collect(),
+            ..sensors::ReadOptions::default()
+        });
+        let mut sensor_table = HashMap::new();
+        reader
+            .read(
+                Cursor::new(AX6_BYTES),
+                |_| {},
+                |tables| {
+                    for table in tables {
+                        sensor_table.insert(table.name, table);
+                    }
+                },
+                false,
+            )
+            .unwrap();
+
+        assert!(!sensor_table.contains_key("low_frequency"));
+        let high = &sensor_table["high_frequency"];
+        assert_eq!(high.datetime.len(), 30 * 40);
+        assert_eq!(high.data.len(), 1);
+        assert_eq!(high.data[0].kind, sensors::SensorKind::Accelerometer);
+    }
+
+    #[test]
+    fn test_axivity_info() {
+        for (data, has_gyro) in [(AX3_BYTES, false), (AX6_BYTES, true)] {
+            let mut sensor_table = HashMap::new();
+            AxivityReader::new()
+                .read(
+                    Cursor::new(data),
+                    |_| {},
+                    |tables| {
+                        for table in tables {
+                            sensor_table.insert(table.name, table);
+                        }
+                    },
+                    false,
+                )
+                .unwrap();
+
+            let mut metadata = HashMap::new();
+            let info = AxivityReader::new()
+                .info(Cursor::new(data), |entry| {
+                    metadata.insert(
+                        (entry.category.to_owned(), entry.key.to_owned()),
+                        entry.value.into_owned(),
+                    );
+                })
+                .unwrap();
+
+            let high = &sensor_table["high_frequency"];
+            assert!(metadata.contains_key(&("device".into(), "hardware_type".into())));
+            assert_eq!(info.first_timestamp, high.datetime.first().copied());
+            assert_eq!(info.last_timestamp, high.datetime.last().copied());
+            assert_eq!(info.estimated_samples, Some(high.datetime.len() as u64));
+            assert_eq!(
+                info.sensors.contains(&sensors::SensorKind::Gyroscope),
+                has_gyro
+            );
+        }
+    }
+
+    #[test]
+    fn test_axivity_reader_events() {
+        for data in [AX3_BYTES, AX6_BYTES] {
+            let mut sensor_table = HashMap::new();
+            let result = AxivityReader::new()
+                .read(
+                    Cursor::new(data),
+                    |_| {},
+                    |tables| {
+                        for table in tables {
+                            sensor_table.insert(table.name, table);
+                        }
+                    },
+                    false,
+                )
+                .unwrap();
+
+            // Both recordings start with a resume event in their first sector.
+            assert_eq!(
+                result.events,
+                [sensors::Event {
+                    time: sensor_table["low_frequency"].datetime[0],
+                    kind: "resume",
+                    payload: vec![],
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn test_axivity_reader_sector_ids() {
+        let sector = |index: usize| &AX3_BYTES[HEADER_SIZE + index * SECTOR_SIZE..][..SECTOR_SIZE];
+        // Drop sector 5, repeat sector 10 and move sectors 20 to 22 and 25 to
+        // another session.
+        let mut data = AX3_BYTES[..HEADER_SIZE].to_vec();
+        for index in 0..30 {
+            match index {
+                5 => continue,
+                10 => data.extend_from_slice(sector(index)),
+                _ => {}
+            }
+            data.extend_from_slice(sector(index));
+            if matches!(index, 20..=22 | 25) {
+                let start = data.len() - SECTOR_SIZE;
+                data[start + 6] ^= 0x01;
+                fix_sector_checksum(&mut data[start..]);
+            }
+        }
+
+        let result = AxivityReader::new()
+            .read(Cursor::new(data), |_| {}, |_| {}, false)
+            .unwrap();
+        assert_eq!(
+            result.warnings,
+            [
+                "sequence ID jumps from 4 to 6 (1 sector missing) at record 5, byte offset 3584",
+                "sequence ID 10 repeated at record 10, byte offset 6144",
+                "3 sectors from session 27 in a file of session 26 \
+                 at record 20, byte offset 11264 to record 22, byte offset 12288",
+                "sector from session 27 in a file of session 26 at record 25, byte offset 13824",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_axivity_annotation() {
+        let annotation = b"?_c=Newcastle&_sh=172.5&_p=Left+wrist";
+        let mut data = AX3_BYTES.to_vec();
+        data[64..512].fill(0x20);
+        data[64..64 + annotation.len()].copy_from_slice(annotation);
+
+        let mut metadata = HashMap::new();
+        AxivityReader::new()
+            .info(Cursor::new(data), |entry| {
+                metadata.insert(
+                    (entry.category.to_owned(), entry.key.to_owned()),
+                    entry.value.into_owned(),
+                );
+            })
+            .unwrap();
+
+        assert_eq!(
+            metadata[&("session".into(), "annotation".into())],
+            MetadataValue::String("?_c=Newcastle&_sh=172.5&_p=Left+wrist".into())
+        );
+        assert_eq!(
+            metadata[&("study".into(), "centre".into())],
+            MetadataValue::String("Newcastle".into())
+        );
+        assert_eq!(
+            metadata[&("subject".into(), "height".into())],
+            MetadataValue::Float(172.5)
+        );
+        assert_eq!(
+            metadata[&("subject".into(), "body_location".into())],
+            MetadataValue::String("Left wrist".into())
+        );
+    }
+
     #[test]
     fn test_invalid_header_magic() {
         let mut reader = AxivityReader::new();
@@ -127,6 +127,23 @@ pub enum ActfastError {
     },
 }
 
+impl ActfastError {
+    /// Where in the file the error occurred, for errors that carry a location
+    pub fn location(&self) -> Option<&FileLocation> {
+        match self {
+            ActfastError::Io { .. }
+            | ActfastError::UnknownFormat { .. }
+            | ActfastError::UnsupportedFormat { .. } => None,
+            ActfastError::Parse { location, .. }
+            | ActfastError::InvalidField { location, .. }
+            | ActfastError::UnexpectedEof { location, .. }
+            | ActfastError::InvalidDateTime { location, .. }
+            | ActfastError::InvalidHex { location, .. }
+            | ActfastError::BitRead { location, .. } => Some(location),
+        }
+    }
+}
+
 impl fmt::Display for ActfastError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -234,13 +251,6 @@ impl<T> IoResultExt<T> for std::result::Result<T, std::io::Error> {
     }
 }
 
-// Conversion to PyErr
-impl From<ActfastError> for pyo3::PyErr {
-    fn from(err: ActfastError) -> pyo3::PyErr {
-        pyo3::exceptions::PyValueError::new_err(err.to_string())
-    }
-}
-
 pub type Result<T> = std::result::Result<T, ActfastError>;
 
 #[cfg(test)]
@@ -265,6 +275,20 @@ mod tests {
         assert!(msg.contains("Unknown file format"));
     }
 
+    #[test]
+    fn test_error_location() {
+        let err = ActfastError::BitRead {
+            context: "accelerometer X".to_string(),
+            location: FileLocation::at_record(3).with_sample(7),
+        };
+        let location = err.location().unwrap();
+        assert_eq!(location.record_index, Some(3));
+        assert_eq!(location.sample_index, Some(7));
+
+        let err = ActfastError::UnknownFormat { magic: [0; 4] };
+        assert!(err.location().is_none());
+    }
+
     #[test]
     fn test_io_error_context() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file missing");
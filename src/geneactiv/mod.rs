@@ -2,11 +2,11 @@
 
 mod defs;
 
-use crate::error::{ActfastError, FileLocation, Result};
+use crate::error::{ActfastError, FileLocation, IoResultExt, Result};
 use crate::geneactiv::defs::*;
-use crate::sensors;
+use crate::sensors::{self, MetadataValue};
 
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
 
 pub struct SampleDataUncalibrated {
     pub x: i16,
@@ -157,38 +157,56 @@ pub struct HighFrequencySensorData {
 }
 
 impl HighFrequencySensorData {
-    pub fn reserve(&mut self, num_measurements: usize) {
+    pub const SENSORS: [sensors::SensorKind; 3] = [
+        sensors::SensorKind::Accelerometer,
+        sensors::SensorKind::Light,
+        sensors::SensorKind::ButtonState,
+    ];
+
+    pub fn reserve(&mut self, num_measurements: usize, selected: sensors::SensorSet) {
         self.time.reserve(num_measurements);
-        self.acceleration.reserve(num_measurements * 3);
-        self.light.reserve(num_measurements);
-        self.button_state.reserve(num_measurements);
+        if selected.contains(sensors::SensorKind::Accelerometer) {
+            self.acceleration.reserve(num_measurements * 3);
+        }
+        if selected.contains(sensors::SensorKind::Light) {
+            self.light.reserve(num_measurements);
+        }
+        if selected.contains(sensors::SensorKind::ButtonState) {
+            self.button_state.reserve(num_measurements);
+        }
     }
 
-    pub fn push(&mut self, time: i64, sample: SampleDataCalibrated) {
+    pub fn push(&mut self, time: i64, sample: SampleDataCalibrated, selected: sensors::SensorSet) {
         self.time.push(time);
-        self.acceleration.push(sample.x);
-        self.acceleration.push(sample.y);
-        self.acceleration.push(sample.z);
-        self.light.push(sample.light);
-        self.button_state.push(sample.button_state);
+        if selected.contains(sensors::SensorKind::Accelerometer) {
+            self.acceleration.push(sample.x);
+            self.acceleration.push(sample.y);
+            self.acceleration.push(sample.z);
+        }
+        if selected.contains(sensors::SensorKind::Light) {
+            self.light.push(sample.light);
+        }
+        if selected.contains(sensors::SensorKind::ButtonState) {
+            self.button_state.push(sample.button_state);
+        }
     }
 
-    pub fn sensor_table(&self) -> sensors::SensorTable<'_> {
+    pub fn take_sensor_table(&mut self) -> sensors::SensorTable {
         sensors::SensorTable {
             name: "high_frequency",
-            datetime: &self.time,
+            datetime: std::mem::take(&mut self.time),
             data: vec![
                 sensors::SensorData {
                     kind: sensors::SensorKind::Accelerometer,
-                    data: sensors::SensorDataDyn::F32(&self.acceleration),
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.acceleration)),
                 },
                 sensors::SensorData {
                     kind: sensors::SensorKind::Light,
-                    data: sensors::SensorDataDyn::F32(&self.light),
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.light)),
                 },
                 sensors::SensorData {
                     kind: sensors::SensorKind::ButtonState,
-                    data: sensors::SensorDataDyn::Bool(&self.button_state),
+                    data: sensors::SensorDataDyn::Bool(std::mem::take(&mut self.button_state)),
                 },
             ],
         }
@@ -203,6 +221,11 @@ pub struct LowFrequencySensorData {
 }
 
 impl LowFrequencySensorData {
+    pub const SENSORS: [sensors::SensorKind; 2] = [
+        sensors::SensorKind::Temperature,
+        sensors::SensorKind::BatteryVoltage,
+    ];
+
     pub fn reserve(&mut self, num_measurements: usize) {
         self.time.reserve(num_measurements);
         self.temperature.reserve(num_measurements);
@@ -215,48 +238,229 @@ impl LowFrequencySensorData {
         self.battery_voltage.push(battery_voltage);
     }
 
-    pub fn sensor_table(&self) -> sensors::SensorTable<'_> {
+    pub fn take_sensor_table(&mut self) -> sensors::SensorTable {
         sensors::SensorTable {
             name: "low_frequency",
-            datetime: &self.time,
+            datetime: std::mem::take(&mut self.time),
             data: vec![
                 sensors::SensorData {
                     kind: sensors::SensorKind::Temperature,
-                    data: sensors::SensorDataDyn::F32(&self.temperature),
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.temperature)),
                 },
                 sensors::SensorData {
                     kind: sensors::SensorKind::BatteryVoltage,
-                    data: sensors::SensorDataDyn::F32(&self.battery_voltage),
+                    data: sensors::SensorDataDyn::F32(std::mem::take(&mut self.battery_voltage)),
                 },
             ],
         }
     }
 }
 
+/// Reader for GENEActiv `.bin` files
 #[derive(Default)]
 pub struct GeneActivReader {
-    pub high_frequency_data: HighFrequencySensorData,
-    pub low_frequency_data: LowFrequencySensorData,
+    high_frequency_data: HighFrequencySensorData,
+    low_frequency_data: LowFrequencySensorData,
+    options: sensors::ReadOptions,
 }
 
 impl GeneActivReader {
+    /// Reader with the default [`ReadOptions`](sensors::ReadOptions)
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn reserve(&mut self, num_records: usize, measurements_per_record: usize) {
-        let num_measurements = num_records * measurements_per_record;
-        self.high_frequency_data.reserve(num_measurements);
-        self.low_frequency_data.reserve(num_records);
+    /// Reader that applies `options`, such as the time window or sensors
+    pub fn with_options(options: sensors::ReadOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    fn reserve(&mut self, num_records: usize, measurements_per_record: usize) {
+        let num_measurements = self
+            .options
+            .reserve_limit(num_records * measurements_per_record);
+        let selected = self.options.sensors;
+        if selected.contains_any(&HighFrequencySensorData::SENSORS) {
+            self.high_frequency_data.reserve(num_measurements, selected);
+        }
+        if selected.contains_any(&LowFrequencySensorData::SENSORS) {
+            self.low_frequency_data
+                .reserve(num_measurements.div_ceil(measurements_per_record.max(1)));
+        }
+    }
+
+    fn emit_sensor_tables<S: FnMut(Vec<sensors::SensorTable>)>(
+        &mut self,
+        sensor_table_callback: &mut S,
+    ) {
+        let tables = [
+            self.low_frequency_data.take_sensor_table(),
+            self.high_frequency_data.take_sensor_table(),
+        ];
+        sensor_table_callback(
+            tables
+                .into_iter()
+                .filter_map(|table| table.select(self.options.sensors))
+                .collect(),
+        );
     }
 }
 
 const HEADER_LINES: usize = 59;
 const RECORD_LINES: usize = 10;
 
-impl<'a> sensors::SensorsFormatReader<'a> for GeneActivReader {
+/// Fields of the file header needed to read the pages
+#[derive(Default)]
+struct Header {
+    number_of_pages: usize,
+    calibration_data: CalibrationData,
+}
+
+/// Type a header value by its key
+fn header_value<'a>(key: &str, value: &'a str) -> MetadataValue<&'a str> {
+    use id::*;
+    match key {
+        calibration::X_GAIN
+        | calibration::X_OFFSET
+        | calibration::Y_GAIN
+        | calibration::Y_OFFSET
+        | calibration::Z_GAIN
+        | calibration::Z_OFFSET
+        | calibration::VOLTS
+        | calibration::LUX
+        | memory::PAGES => MetadataValue::int_or_str(value),
+        capabilities::ACCELEROMETER_RESOLUTION
+        | capabilities::TEMPERATURE_SENSOR_RESOLUTION
+        | subject::HEIGHT
+        | subject::WEIGHT => MetadataValue::float_or_str(value),
+        // "60 Hz", "576 Hours"
+        configuration::MEASUREMENT_FREQUENCY | configuration::MEASUREMENT_PERIOD => value
+            .split_whitespace()
+            .next()
+            .and_then(|number| number.parse().ok())
+            .map_or(MetadataValue::String(value), MetadataValue::Float),
+        identity::CALIBRATION_DATE
+        | configuration::START_TIME
+        | trial::CONFIG_TIME
+        | trial::EXTRACT_TIME => parse_date_time(value, FileLocation::new())
+            .map_or(MetadataValue::String(value), MetadataValue::DateTime),
+        _ => MetadataValue::String(value),
+    }
+}
+
+/// Read the 59-line file header, emitting every entry as metadata
+fn read_header<R: BufRead, M: FnMut(sensors::MetadataEntry)>(
+    reader: &mut R,
+    mut metadata_callback: M,
+) -> Result<Header> {
+    let mut header = Header::default();
+
+    // Read header (59 lines)
+    let mut lines_header = vec![String::new(); HEADER_LINES];
+    let lines_read = read_n_lines(reader, &mut lines_header, 1)?;
+
+    if lines_read < HEADER_LINES {
+        return Err(ActfastError::UnexpectedEof {
+            context: format!(
+                "while reading header (expected {} lines, got {})",
+                HEADER_LINES, lines_read
+            ),
+            location: FileLocation::at_line(lines_read),
+        });
+    }
+
+    let mut last_category = String::new();
+    for line in &lines_header {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let colon = match line.find(':') {
+            Some(pos) => pos,
+            None => {
+                last_category = line.to_string();
+                continue;
+            }
+        };
+
+        let entry = sensors::MetadataEntry {
+            category: &last_category,
+            key: &line[..colon],
+            value: header_value(&line[..colon], &line[colon + 1..]),
+        };
+
+        // Extract number of pages for data reservation
+        if entry.category == defs::id::memory::HEADER {
+            if let Some(pages) = parse_value(line, id::memory::PAGES, 1) {
+                header.number_of_pages = pages;
+            }
+        }
+        // Extract calibration data
+        else if entry.category == defs::id::calibration::HEADER {
+            if let Some(v) = parse_value(line, id::calibration::X_GAIN, 1) {
+                header.calibration_data.x_gain = v;
+            } else if let Some(v) = parse_value(line, id::calibration::X_OFFSET, 1) {
+                header.calibration_data.x_offset = v;
+            } else if let Some(v) = parse_value(line, id::calibration::Y_GAIN, 1) {
+                header.calibration_data.y_gain = v;
+            } else if let Some(v) = parse_value(line, id::calibration::Y_OFFSET, 1) {
+                header.calibration_data.y_offset = v;
+            } else if let Some(v) = parse_value(line, id::calibration::Z_GAIN, 1) {
+                header.calibration_data.z_gain = v;
+            } else if let Some(v) = parse_value(line, id::calibration::Z_OFFSET, 1) {
+                header.calibration_data.z_offset = v;
+            } else if let Some(v) = parse_value(line, id::calibration::VOLTS, 1) {
+                header.calibration_data.volts = v;
+            } else if let Some(v) = parse_value(line, id::calibration::LUX, 1) {
+                header.calibration_data.lux = v;
+            }
+        }
+
+        metadata_callback(entry);
+    }
+
+    Ok(header)
+}
+
+/// Fields of a page header (the nine lines before the sample data)
+struct PageHeader {
+    time: chrono::DateTime<chrono::Utc>,
+    measurement_frequency: f32,
+    temperature: f32,
+    battery_voltage: f32,
+}
+
+fn parse_page_header(lines: &[String], location: &FileLocation) -> Result<PageHeader> {
+    let mut page_header = PageHeader {
+        time: chrono::DateTime::<chrono::Utc>::from_timestamp(0, 0).unwrap(),
+        measurement_frequency: 1.0,
+        temperature: 0.0,
+        battery_voltage: 0.0,
+    };
+
+    for line in lines.iter().take(9) {
+        let line = line.trim();
+        if let Some(freq) = parse_value(line, id::record::MEASUREMENT_FREQUENCY, 1) {
+            page_header.measurement_frequency = freq;
+        } else if let Some(time_str) = read_prefixed(line, id::record::PAGE_TIME, 1) {
+            page_header.time = defs::parse_date_time(time_str.trim(), location.clone())?;
+        } else if let Some(temp) = parse_value(line, id::record::TEMPERATURE, 1) {
+            page_header.temperature = temp;
+        } else if let Some(bv) = parse_value(line, id::record::BATTERY_VOLTAGE, 1) {
+            page_header.battery_voltage = bv;
+        }
+    }
+
+    Ok(page_header)
+}
+
+impl sensors::SensorsFormatReader for GeneActivReader {
     fn read<R: std::io::Read + std::io::Seek, M, S>(
-        &'a mut self,
+        &mut self,
         reader: R,
         mut metadata_callback: M,
         mut sensor_table_callback: S,
@@ -264,86 +468,27 @@ impl<'a> sensors::SensorsFormatReader<'a> for GeneActivReader {
     ) -> Result<sensors::ReadResult>
     where
         M: FnMut(sensors::MetadataEntry),
-        S: FnMut(sensors::SensorTable<'a>),
+        S: FnMut(Vec<sensors::SensorTable>),
     {
         let mut result = sensors::ReadResult::new();
         let mut buf_reader = BufReader::new(reader);
 
-        let mut number_of_pages: usize = 0;
         let mut data_reserved = false;
-        let mut calibration_data = CalibrationData::default();
-
-        // Read header (59 lines)
-        let mut lines_header = vec![String::new(); HEADER_LINES];
-        let lines_read = read_n_lines(&mut buf_reader, &mut lines_header, 1)?;
-
-        if lines_read < HEADER_LINES {
-            return Err(ActfastError::UnexpectedEof {
-                context: format!(
-                    "while reading header (expected {} lines, got {})",
-                    HEADER_LINES, lines_read
-                ),
-                location: FileLocation::at_line(lines_read),
-            });
-        }
-
-        let mut last_category = String::new();
-        for line in &lines_header {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            let colon = match line.find(':') {
-                Some(pos) => pos,
-                None => {
-                    last_category = line.to_string();
-                    continue;
-                }
-            };
-
-            let entry = sensors::MetadataEntry {
-                category: &last_category,
-                key: &line[..colon],
-                value: &line[colon + 1..],
-            };
-
-            // Extract number of pages for data reservation
-            if entry.category == defs::id::memory::HEADER {
-                if let Some(pages) = parse_value(line, id::memory::PAGES, 1) {
-                    number_of_pages = pages;
-                }
-            }
-            // Extract calibration data
-            else if entry.category == defs::id::calibration::HEADER {
-                if let Some(v) = parse_value(line, id::calibration::X_GAIN, 1) {
-                    calibration_data.x_gain = v;
-                } else if let Some(v) = parse_value(line, id::calibration::X_OFFSET, 1) {
-                    calibration_data.x_offset = v;
-                } else if let Some(v) = parse_value(line, id::calibration::Y_GAIN, 1) {
-                    calibration_data.y_gain = v;
-                } else if let Some(v) = parse_value(line, id::calibration::Y_OFFSET, 1) {
-                    calibration_data.y_offset = v;
-                } else if let Some(v) = parse_value(line, id::calibration::Z_GAIN, 1) {
-                    calibration_data.z_gain = v;
-                } else if let Some(v) = parse_value(line, id::calibration::Z_OFFSET, 1) {
-                    calibration_data.z_offset = v;
-                } else if let Some(v) = parse_value(line, id::calibration::VOLTS, 1) {
-                    calibration_data.volts = v;
-                } else if let Some(v) = parse_value(line, id::calibration::LUX, 1) {
-                    calibration_data.lux = v;
-                }
-            }
-
-            metadata_callback(entry);
-        }
+        let Header {
+            number_of_pages,
+            calibration_data,
+        } = read_header(&mut buf_reader, &mut metadata_callback)?;
 
         // Read data records
         let mut lines_record = vec![String::new(); RECORD_LINES];
         let mut current_line = HEADER_LINES + 1;
         let mut record_index: usize = 0;
 
-        'records: loop {
+        let selected = self.options.sensors;
+        let decode_high_frequency = selected.contains_any(&HighFrequencySensorData::SENSORS);
+        let decode_low_frequency = selected.contains_any(&LowFrequencySensorData::SENSORS);
+
+        loop {
             let lines_read = read_n_lines(&mut buf_reader, &mut lines_record, current_line)?;
             if lines_read == 0 {
                 break; // Normal EOF
@@ -372,34 +517,22 @@ impl<'a> sensors::SensorsFormatReader<'a> for GeneActivReader {
                 data_reserved = true;
             }
 
-            // Parse record header
-            let mut measurement_frequency: f32 = 1.0;
-            let mut page_time = chrono::DateTime::<chrono::Utc>::from_timestamp(0, 0).unwrap();
-            let mut temperature: f32 = 0.0;
-            let mut battery_voltage: f32 = 0.0;
-
-            for line in lines_record.iter().take(9) {
-                let line = line.trim();
-                if let Some(freq) = parse_value(line, id::record::MEASUREMENT_FREQUENCY, 1) {
-                    measurement_frequency = freq;
-                } else if let Some(time_str) = read_prefixed(line, id::record::PAGE_TIME, 1) {
-                    match defs::parse_date_time(time_str.trim(), record_location.clone()) {
-                        Ok(dt) => page_time = dt,
-                        Err(e) => {
-                            if lenient {
-                                result.warnings.push(e.to_string());
-                                continue 'records;
-                            } else {
-                                return Err(e);
-                            }
-                        }
+            let PageHeader {
+                time: page_time,
+                measurement_frequency,
+                temperature,
+                battery_voltage,
+            } = match parse_page_header(&lines_record, &record_location) {
+                Ok(page_header) => page_header,
+                Err(e) => {
+                    if lenient {
+                        result.warnings.push(e.to_string());
+                        continue;
+                    } else {
+                        return Err(e);
                     }
-                } else if let Some(temp) = parse_value(line, id::record::TEMPERATURE, 1) {
-                    temperature = temp;
-                } else if let Some(bv) = parse_value(line, id::record::BATTERY_VOLTAGE, 1) {
-                    battery_voltage = bv;
                 }
-            }
+            };
 
             let page_time_nanos = match page_time.timestamp_nanos_opt() {
                 Some(nanos) => nanos,
@@ -418,54 +551,162 @@ impl<'a> sensors::SensorsFormatReader<'a> for GeneActivReader {
                 }
             };
 
-            self.low_frequency_data
-                .push(page_time_nanos, temperature, battery_voltage);
-
-            // Parse sample data (hex-encoded binary)
+            // Pages are time-ordered: skip whole pages outside the window
+            // without decoding their sample data.
             let hex_data = lines_record[9].trim();
-            let buf = match decode_hex(hex_data, record_location.clone()) {
-                Ok(buf) => buf,
-                Err(e) => {
-                    if lenient {
-                        result.warnings.push(e.to_string());
-                        continue;
-                    } else {
-                        return Err(e);
-                    }
-                }
-            };
-            let mut bitreader = bitreader::BitReader::new(buf.as_slice());
+            let sample_offset_nanos = (1_000_000_000.0 / measurement_frequency) as i64;
+            let page_end_nanos =
+                page_time_nanos + sample_offset_nanos * (hex_data.len() / 12) as i64;
+            if self.options.after_window(page_time_nanos) {
+                break;
+            }
+            if !self
+                .options
+                .overlaps_window(page_time_nanos, page_end_nanos)
+            {
+                current_line += RECORD_LINES;
+                record_index += 1;
+                continue;
+            }
+
+            if decode_low_frequency && self.options.in_window(page_time_nanos) {
+                self.low_frequency_data
+                    .push(page_time_nanos, temperature, battery_voltage);
+            }
 
-            let num_samples = buf.len() / 6;
-            for sample_idx in 0..num_samples {
-                let sample_location = record_location.clone().with_sample(sample_idx);
-                let sample = match SampleDataUncalibrated::read(&mut bitreader, &sample_location) {
-                    Ok(s) => s.calibrate(&calibration_data),
+            // Without high-frequency sensors, only the page header is needed.
+            if decode_high_frequency {
+                // Parse sample data (hex-encoded binary)
+                let buf = match decode_hex(hex_data, record_location.clone()) {
+                    Ok(buf) => buf,
                     Err(e) => {
                         if lenient {
                             result.warnings.push(e.to_string());
-                            break; // Skip rest of samples in this record
+                            continue;
                         } else {
                             return Err(e);
                         }
                     }
                 };
+                let mut bitreader = bitreader::BitReader::new(buf.as_slice());
+
+                let num_samples = buf.len() / 6;
+                for sample_idx in 0..num_samples {
+                    let sample_location = record_location.clone().with_sample(sample_idx);
+                    let sample =
+                        match SampleDataUncalibrated::read(&mut bitreader, &sample_location) {
+                            Ok(s) => s.calibrate(&calibration_data),
+                            Err(e) => {
+                                if lenient {
+                                    result.warnings.push(e.to_string());
+                                    break; // Skip rest of samples in this record
+                                } else {
+                                    return Err(e);
+                                }
+                            }
+                        };
 
-                let sample_offset_nanos = (1_000_000_000.0 / measurement_frequency) as i64;
-                let sample_time_nanos = page_time_nanos + sample_offset_nanos * sample_idx as i64;
+                    let sample_time_nanos =
+                        page_time_nanos + sample_offset_nanos * sample_idx as i64;
+                    if !self.options.in_window(sample_time_nanos) {
+                        continue;
+                    }
 
-                self.high_frequency_data.push(sample_time_nanos, sample);
+                    self.high_frequency_data
+                        .push(sample_time_nanos, sample, selected);
+                }
             }
 
             current_line += RECORD_LINES;
             record_index += 1;
+
+            if self.options.chunk_full(
+                self.high_frequency_data
+                    .time
+                    .len()
+                    .max(self.low_frequency_data.time.len()),
+            ) {
+                self.emit_sensor_tables(&mut sensor_table_callback);
+                if self.options.cancelled() {
+                    return Ok(result);
+                }
+                data_reserved = false;
+            }
         }
 
-        sensor_table_callback(self.low_frequency_data.sensor_table());
-        sensor_table_callback(self.high_frequency_data.sensor_table());
+        self.emit_sensor_tables(&mut sensor_table_callback);
 
         Ok(result)
     }
+
+    fn info<R: std::io::Read + std::io::Seek, M>(
+        &mut self,
+        reader: R,
+        mut metadata_callback: M,
+    ) -> Result<sensors::RecordingInfo>
+    where
+        M: FnMut(sensors::MetadataEntry),
+    {
+        let mut buf_reader = BufReader::new(reader);
+        let header = read_header(&mut buf_reader, &mut metadata_callback)?;
+        let data_start = buf_reader
+            .stream_position()
+            .with_context("locating the first page")?;
+
+        let mut info = sensors::RecordingInfo::default();
+        info.sensors.extend(HighFrequencySensorData::SENSORS);
+        info.sensors.extend(LowFrequencySensorData::SENSORS);
+
+        let mut lines_record = vec![String::new(); RECORD_LINES];
+        if read_n_lines(&mut buf_reader, &mut lines_record, HEADER_LINES + 1)? < RECORD_LINES {
+            return Ok(info);
+        }
+        let first_page = parse_page_header(&lines_record, &FileLocation::at_record(0))?;
+        let samples_per_page = lines_record[9].trim().len() / 12;
+        let page_bytes: usize = lines_record.iter().map(|line| line.len()).sum();
+
+        info.first_timestamp = first_page.time.timestamp_nanos_opt();
+        info.sample_rate = Some(first_page.measurement_frequency as f64);
+
+        // Pages have (almost) the same size, so the last complete page starts
+        // within the final two page lengths of the file.
+        let file_len = buf_reader
+            .seek(SeekFrom::End(0))
+            .with_context("seeking to the end of the file")?;
+        let tail_start = file_len
+            .saturating_sub(2 * page_bytes as u64)
+            .max(data_start);
+        buf_reader
+            .seek(SeekFrom::Start(tail_start))
+            .with_context("seeking to the last page")?;
+        let mut tail = Vec::new();
+        for line in buf_reader.lines() {
+            tail.push(line.with_context("reading the last page")?);
+        }
+
+        let last_page_start = (0..tail.len())
+            .rev()
+            .find(|&i| tail[i].trim() == id::record::HEADER && i + RECORD_LINES <= tail.len());
+        if let Some(i) = last_page_start {
+            let lines = &tail[i..i + RECORD_LINES];
+            let location = FileLocation::at_record(header.number_of_pages.saturating_sub(1));
+            let last_page = parse_page_header(lines, &location)?;
+            let samples = (lines[9].trim().len() / 12) as i64;
+            let sample_offset_nanos = (1_000_000_000.0 / last_page.measurement_frequency) as i64;
+            info.last_timestamp = last_page
+                .time
+                .timestamp_nanos_opt()
+                .map(|t| t + sample_offset_nanos * (samples - 1).max(0));
+        }
+
+        info.estimated_samples = if header.number_of_pages > 0 {
+            Some((header.number_of_pages * samples_per_page) as u64)
+        } else {
+            info.estimate_samples()
+        };
+
+        Ok(info)
+    }
 }
 
 #[cfg(test)]
@@ -492,6 +733,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_header_value() {
+        // Serials keep their leading zeros
+        assert_eq!(
+            header_value(id::identity::SERIAL, "012345"),
+            MetadataValue::String("012345")
+        );
+        assert_eq!(
+            header_value(id::calibration::X_OFFSET, "-12"),
+            MetadataValue::Int(-12)
+        );
+    }
+
     #[test]
     fn test_geneactiv_reader() {
         let mut reader = GeneActivReader::new();
@@ -503,11 +757,13 @@ mod tests {
             |entry| {
                 metadata.insert(
                     (entry.category.to_owned(), entry.key.to_owned()),
-                    entry.value.to_owned(),
+                    entry.value.into_owned(),
                 );
             },
-            |table| {
-                sensor_table.insert(table.name, table);
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
             },
             false,
         );
@@ -517,6 +773,36 @@ mod tests {
         assert_eq!(metadata.len(), 45);
         assert_eq!(sensor_table.len(), 2);
 
+        let value = |category: &str, key: &str| &metadata[&(category.into(), key.into())];
+        assert_eq!(
+            value("Configuration Info", "Measurement Frequency"),
+            &MetadataValue::Float(60.0)
+        );
+        assert_eq!(
+            value("Configuration Info", "Start Time"),
+            &MetadataValue::DateTime(
+                chrono::DateTime::parse_from_rfc3339("2024-04-30T15:13:30Z")
+                    .unwrap()
+                    .to_utc()
+            )
+        );
+        assert_eq!(
+            value("Device Identity", "Device Unique Serial Code"),
+            &MetadataValue::String("101806".into())
+        );
+        assert_eq!(
+            value("Calibration Data", "x offset"),
+            &MetadataValue::Int(-1846)
+        );
+        assert_eq!(
+            value("Device Identity", "Device Model"),
+            &MetadataValue::String("1.2".into())
+        );
+        assert_eq!(
+            value("Subject Info", "Height"),
+            &MetadataValue::String("".into())
+        );
+
         let low_frequency = sensor_table.get("low_frequency").unwrap();
         assert_eq!(low_frequency.datetime.len(), 20);
         assert_eq!(low_frequency.data.len(), 2);
@@ -639,6 +925,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_geneactiv_reader_window() {
+        let data = include_bytes!("../../test_data/cmi/geneactiv.bin");
+        let start = 1714490030 * 1_000_000_000;
+        let end = 1714490050 * 1_000_000_000;
+        let mut reader = GeneActivReader::with_options(sensors::ReadOptions {
+            start: Some(start),
+            end: Some(end),
+            ..sensors::ReadOptions::default()
+        });
+        let mut sensor_table = HashMap::new();
+        let result = reader.read(
+            Cursor::new(data),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
+            },
+            false,
+        );
+        assert!(result.is_ok());
+
+        // 20 seconds at 60 Hz, pages every 5 seconds
+        let high_frequency = &sensor_table["high_frequency"];
+        assert_eq!(high_frequency.datetime.len(), 1200);
+        assert_eq!(high_frequency.datetime[0], start);
+        assert!(high_frequency.datetime.iter().all(|&t| t < end));
+
+        let low_frequency = &sensor_table["low_frequency"];
+        assert_eq!(low_frequency.datetime.len(), 4);
+        assert_eq!(low_frequency.datetime[0], start);
+    }
+
+    #[test]
+    fn test_geneactiv_reader_sensor_selection() {
+        let data = include_bytes!("../../test_data/cmi/geneactiv.bin");
+        let mut reader = GeneActivReader::with_options(sensors::ReadOptions {
+            sensors: [sensors::SensorKind::Temperature].into_iter().collect(),
+            ..sensors::ReadOptions::default()
+        });
+        let mut sensor_table = HashMap::new();
+        let result = reader.read(
+            Cursor::new(data),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    sensor_table.insert(table.name, table);
+                }
+            },
+            false,
+        );
+        assert!(result.is_ok());
+
+        // High-frequency samples are not decoded at all
+        assert!(!sensor_table.contains_key("high_frequency"));
+        let low_frequency = &sensor_table["low_frequency"];
+        assert_eq!(low_frequency.datetime.len(), 20);
+        assert_eq!(low_frequency.data.len(), 1);
+        assert_eq!(low_frequency.data[0].kind, sensors::SensorKind::Temperature);
+    }
+
+    #[test]
+    fn test_geneactiv_info() {
+        let data = include_bytes!("../../test_data/cmi/geneactiv.bin");
+        let mut read_metadata = HashMap::new();
+        let mut sensor_table = HashMap::new();
+        GeneActivReader::new()
+            .read(
+                Cursor::new(data),
+                |entry| {
+                    read_metadata.insert(
+                        (entry.category.to_owned(), entry.key.to_owned()),
+                        entry.value.into_owned(),
+                    );
+                },
+                |tables| {
+                    for table in tables {
+                        sensor_table.insert(table.name, table);
+                    }
+                },
+                false,
+            )
+            .unwrap();
+
+        let mut metadata = HashMap::new();
+        let info = GeneActivReader::new()
+            .info(Cursor::new(data), |entry| {
+                metadata.insert(
+                    (entry.category.to_owned(), entry.key.to_owned()),
+                    entry.value.into_owned(),
+                );
+            })
+            .unwrap();
+
+        let high_frequency = &sensor_table["high_frequency"];
+        assert_eq!(metadata, read_metadata);
+        assert_eq!(
+            info.first_timestamp,
+            high_frequency.datetime.first().copied()
+        );
+        assert_eq!(info.last_timestamp, high_frequency.datetime.last().copied());
+        assert_eq!(info.sample_rate, Some(60.0));
+        assert_eq!(info.estimated_samples, Some(6000));
+        assert_eq!(info.sensors.len(), 5);
+    }
+
     #[test]
     fn test_invalid_hex() {
         let result = decode_hex("GGGG", FileLocation::new());
@@ -1,165 +1,313 @@
+//! Fast actigraphy data reader
+//!
+//! Reads raw sensor data from ActiGraph (GT3X), GENEActiv (BIN) and Axivity
+//! (CWA) files. The Python bindings are built with the `python` feature.
+//!
+//! ```no_run
+//! use actfast::{ReadOptions, Recording};
+//!
+//! let recording = Recording::open("subject1.cwa", ReadOptions::default(), false)?;
+//! for table in &recording.tables {
+//!     println!("{}: {} samples", table.name, table.datetime.len());
+//! }
+//! # Ok::<(), actfast::ActfastError>(())
+//! ```
+
 mod actigraph;
 mod axivity;
 mod error;
 mod file_format;
 mod geneactiv;
 mod sensors;
+mod stream;
 
-use std::io::Read;
-
-use numpy::{PyArray1, prelude::*};
-use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
-
-use error::{ActfastError, IoResultExt};
-use sensors::SensorsFormatReader;
-
-/// Convert a slice to a numpy array, reshaping for multi-axis sensors
-fn sensor_data_to_pyarray<'py, T>(
-    py: Python<'py>,
-    data: &[T],
-    reference_len: usize,
-) -> PyResult<Bound<'py, PyAny>>
-where
-    T: numpy::Element,
-{
-    let arr = PyArray1::from_slice(py, data);
-    if reference_len == 0 || data.len() == reference_len {
-        return Ok(arr.into_any());
-    }
+#[cfg(feature = "python")]
+mod python;
 
-    let num_channels = data.len() / reference_len;
-    Ok(arr.reshape([reference_len, num_channels])?.into_any())
-}
+use std::io::{Read, Seek};
+use std::path::Path;
 
-/// Convert SensorDataDyn to numpy array using a macro to reduce repetition
-macro_rules! sensor_data_dyn_to_pyarray {
-    ($py:expr, $data:expr, $ref_len:expr) => {
-        match $data {
-            sensors::SensorDataDyn::F32(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::F64(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::U8(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::U16(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::U32(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::U64(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::I8(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::I16(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::I32(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::I64(d) => sensor_data_to_pyarray($py, d, $ref_len),
-            sensors::SensorDataDyn::Bool(d) => sensor_data_to_pyarray($py, d, $ref_len),
-        }
-    };
-}
+pub use actigraph::ActigraphReader;
+pub use axivity::AxivityReader;
+pub use error::{ActfastError, FileLocation, Result};
+pub use file_format::FileFormat;
+pub use geneactiv::GeneActivReader;
+pub use sensors::{
+    Event, IdleSleep, MetadataEntry, MetadataValue, OwnedMetadataEntry, ReadOptions, ReadResult,
+    RecordingInfo, Segment, SensorData, SensorDataDyn, SensorKind, SensorSet, SensorTable,
+    SensorsFormatReader, Timestamps,
+};
+pub use stream::{Chunk, ChunkIterator, DEFAULT_CHUNK_SAMPLES};
 
-#[pyfunction]
-#[pyo3(signature = (path, lenient=false))]
-fn read(py: Python, path: std::path::PathBuf, lenient: bool) -> PyResult<Py<PyAny>> {
-    let file = std::fs::File::open(&path).with_context(format!("opening '{}'", path.display()))?;
+use error::IoResultExt;
 
-    let mut reader = std::io::BufReader::new(file);
+/// Identify the format of `reader` from its magic bytes, leaving it rewound
+pub fn identify<R: Read + Seek>(reader: &mut R) -> Result<FileFormat> {
     let mut magic = [0u8; 4];
     reader
         .read_exact(&mut magic)
         .with_context("reading file header")?;
+    reader
+        .rewind()
+        .with_context("rewinding after file header")?;
 
-    let format_type = file_format::identify(&magic).ok_or(ActfastError::UnknownFormat { magic })?;
-
-    let dict = PyDict::new(py);
-    let dict_metadata = PyDict::new(py);
-    let dict_timeseries = PyDict::new(py);
-
-    let metadata_callback = |metadata: sensors::MetadataEntry| {
-        let category_dict = dict_metadata
-            .get_item(metadata.category)
-            .ok()
-            .flatten()
-            .map(|item| item.cast::<PyDict>().unwrap().clone())
-            .unwrap_or_else(|| {
-                let d = PyDict::new(py);
-                dict_metadata.set_item(metadata.category, &d).unwrap();
-                d
-            });
-        category_dict
-            .set_item(metadata.key, metadata.value)
-            .unwrap();
-    };
-
-    let sensor_table_callback = |sensor_table: sensors::SensorTable| {
-        let dict_sensor_table = PyDict::new(py);
-        let np_datetime = PyArray1::from_slice(py, sensor_table.datetime);
-        dict_sensor_table.set_item("datetime", np_datetime).unwrap();
-
-        for sensor_data in sensor_table.data.iter() {
-            let key = sensor_data.kind.as_str();
-            let np_array =
-                sensor_data_dyn_to_pyarray!(py, &sensor_data.data, sensor_table.datetime.len())
-                    .unwrap();
-            dict_sensor_table.set_item(key, np_array).unwrap();
-        }
-        dict_timeseries
-            .set_item(sensor_table.name, dict_sensor_table)
-            .unwrap();
-    };
-
-    // Re-open file for the actual reader (they need fresh file handle)
-    let file =
-        std::fs::File::open(&path).with_context(format!("reopening '{}'", path.display()))?;
-
-    let read_result = match format_type {
-        file_format::FileFormat::ActigraphGt3x => actigraph::ActigraphReader::new().read(
-            file,
+    file_format::identify(&magic).ok_or(ActfastError::UnknownFormat { magic })
+}
+
+/// Open a file and identify its format from the magic bytes
+pub(crate) fn open_and_identify(path: &Path) -> Result<(FileFormat, std::fs::File)> {
+    let mut file =
+        std::fs::File::open(path).with_context(format!("opening '{}'", path.display()))?;
+    let format_type = identify(&mut file)?;
+    Ok((format_type, file))
+}
+
+/// Fail early for formats that are recognized but cannot be read
+pub(crate) fn check_supported(format_type: FileFormat) -> Result<()> {
+    match format_type {
+        FileFormat::ActigraphGt3x | FileFormat::GeneactivBin | FileFormat::AxivityCwa => Ok(()),
+        FileFormat::UnknownWav => Err(ActfastError::UnsupportedFormat {
+            format: format_type,
+            suggestion: "Use a general purpose audio reader (such as Python's 'wave' module)",
+        }),
+        FileFormat::UnknownSqlite => Err(ActfastError::UnsupportedFormat {
+            format: format_type,
+            suggestion: "Use a general purpose SQLite reader (such as Python's 'sqlite3' module)",
+        }),
+        _ => Err(ActfastError::UnsupportedFormat {
+            format: format_type,
+            suggestion: "This format is recognized but not yet implemented",
+        }),
+    }
+}
+
+/// Run the reader for `format_type` over `reader`
+///
+/// `sensor_table_callback` is called once per chunk (see
+/// [`ReadOptions::chunk_samples`]) with the tables decoded since the previous
+/// call, or once in total when not chunking.
+pub fn read_format<R, M, S>(
+    format_type: FileFormat,
+    reader: R,
+    options: sensors::ReadOptions,
+    metadata_callback: M,
+    sensor_table_callback: S,
+    lenient: bool,
+) -> Result<sensors::ReadResult>
+where
+    R: Read + Seek,
+    M: FnMut(sensors::MetadataEntry),
+    S: FnMut(Vec<sensors::SensorTable>),
+{
+    check_supported(format_type)?;
+    match format_type {
+        FileFormat::ActigraphGt3x => actigraph::ActigraphReader::with_options(options).read(
+            reader,
             metadata_callback,
             sensor_table_callback,
             lenient,
-        )?,
-        file_format::FileFormat::GeneactivBin => geneactiv::GeneActivReader::new().read(
-            file,
+        ),
+        FileFormat::GeneactivBin => geneactiv::GeneActivReader::with_options(options).read(
+            reader,
             metadata_callback,
             sensor_table_callback,
             lenient,
-        )?,
-        file_format::FileFormat::AxivityCwa => axivity::AxivityReader::new().read(
-            file,
+        ),
+        FileFormat::AxivityCwa => axivity::AxivityReader::with_options(options).read(
+            reader,
             metadata_callback,
             sensor_table_callback,
             lenient,
-        )?,
-        file_format::FileFormat::UnknownWav => {
-            return Err(ActfastError::UnsupportedFormat {
-                format: format_type,
-                suggestion: "Use a general purpose audio reader (such as Python's 'wave' module)",
-            }
-            .into());
-        }
-        file_format::FileFormat::UnknownSqlite => {
-            return Err(ActfastError::UnsupportedFormat {
-                format: format_type,
-                suggestion: "Use a general purpose SQLite reader (such as Python's 'sqlite3' module)",
-            }
-            .into());
+        ),
+        _ => unreachable!("checked by check_supported"),
+    }
+}
+
+/// Run the metadata-only pass of the reader for `format_type` over `reader`
+pub fn info_format<R, M>(
+    format_type: FileFormat,
+    reader: R,
+    metadata_callback: M,
+) -> Result<sensors::RecordingInfo>
+where
+    R: Read + Seek,
+    M: FnMut(sensors::MetadataEntry),
+{
+    check_supported(format_type)?;
+    match format_type {
+        FileFormat::ActigraphGt3x => {
+            actigraph::ActigraphReader::new().info(reader, metadata_callback)
         }
-        _ => {
-            return Err(ActfastError::UnsupportedFormat {
-                format: format_type,
-                suggestion: "This format is recognized but not yet implemented",
-            }
-            .into());
+        FileFormat::GeneactivBin => {
+            geneactiv::GeneActivReader::new().info(reader, metadata_callback)
         }
-    };
+        FileFormat::AxivityCwa => axivity::AxivityReader::new().info(reader, metadata_callback),
+        _ => unreachable!("checked by check_supported"),
+    }
+}
+
+/// A fully decoded recording
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub format: FileFormat,
+    pub metadata: Vec<OwnedMetadataEntry>,
+    pub tables: Vec<SensorTable>,
+    /// Warnings about the data, see [`ReadResult::warnings`]
+    pub warnings: Vec<String>,
+    /// Spans filled with reconstructed samples, see [`ReadOptions::idle_sleep`]
+    pub filled_spans: Vec<(i64, i64)>,
+    /// Device events, see [`ReadResult::events`]
+    pub events: Vec<Event>,
+    /// See [`ReadResult::checksum_mismatches`]
+    pub checksum_mismatches: usize,
+    /// See [`ReadResult::effective_sample_rate`]
+    pub effective_sample_rate: Option<f64>,
+    /// See [`ReadResult::segments`]
+    pub segments: Vec<Segment>,
+}
+
+impl Recording {
+    /// Read a recording from `reader`, identifying its format first
+    pub fn read<R: Read + Seek>(
+        mut reader: R,
+        options: ReadOptions,
+        lenient: bool,
+    ) -> Result<Self> {
+        let format_type = identify(&mut reader)?;
+        Self::read_format(format_type, reader, options, lenient)
+    }
+
+    /// Read the recording stored in the file at `path`
+    pub fn open(path: impl AsRef<Path>, options: ReadOptions, lenient: bool) -> Result<Self> {
+        let (format_type, file) = open_and_identify(path.as_ref())?;
+        Self::read_format(format_type, file, options, lenient)
+    }
+
+    /// Read a recording from `reader`, which holds a file of format `format_type`
+    pub fn read_format<R: Read + Seek>(
+        format_type: FileFormat,
+        reader: R,
+        options: ReadOptions,
+        lenient: bool,
+    ) -> Result<Self> {
+        let mut metadata = Vec::new();
+        let mut tables = Vec::new();
+        let read_result = read_format(
+            format_type,
+            reader,
+            options,
+            |entry| metadata.push(entry.into()),
+            |chunk| tables.extend(chunk),
+            lenient,
+        )?;
+        Ok(Self {
+            format: format_type,
+            metadata,
+            tables,
+            warnings: read_result.warnings,
+            filled_spans: read_result.filled_spans,
+            events: read_result.events,
+            checksum_mismatches: read_result.checksum_mismatches,
+            effective_sample_rate: read_result.effective_sample_rate,
+            segments: read_result.segments,
+        })
+    }
+
+    /// Read the metadata and summary of the file at `path`, skipping sample data
+    pub fn info(path: impl AsRef<Path>) -> Result<RecordingSummary> {
+        let (format_type, file) = open_and_identify(path.as_ref())?;
+        RecordingSummary::read_format(format_type, file)
+    }
 
-    dict.set_item("format", format_type.to_string())?;
-    dict.set_item("timeseries", dict_timeseries)?;
-    dict.set_item("metadata", dict_metadata)?;
+    /// Look up a sensor table by name
+    pub fn table(&self, name: &str) -> Option<&SensorTable> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+}
 
-    // Add warnings if any
-    let warnings_list = PyList::new(py, &read_result.warnings)?;
-    dict.set_item("warnings", warnings_list)?;
+/// The metadata and [`RecordingInfo`] of a recording, see [`Recording::info`]
+#[derive(Debug, Clone)]
+pub struct RecordingSummary {
+    pub format: FileFormat,
+    pub metadata: Vec<OwnedMetadataEntry>,
+    pub info: RecordingInfo,
+}
 
-    Ok(dict.into())
+impl RecordingSummary {
+    /// Summarise `reader`, which holds a file of format `format_type`
+    pub fn read_format<R: Read + Seek>(format_type: FileFormat, reader: R) -> Result<Self> {
+        let mut metadata = Vec::new();
+        let info = info_format(format_type, reader, |entry| metadata.push(entry.into()))?;
+        Ok(Self {
+            format: format_type,
+            metadata,
+            info,
+        })
+    }
 }
 
-#[pymodule]
-fn actfast(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(read, m)?)?;
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_recording_read_matches_open() {
+        let path = "test_data/cmi/geneactiv.bin";
+        let opened = Recording::open(path, ReadOptions::default(), false).unwrap();
+        let data = std::fs::read(path).unwrap();
+        let read = Recording::read(Cursor::new(data), ReadOptions::default(), false).unwrap();
+
+        assert_eq!(opened.format, FileFormat::GeneactivBin);
+        assert_eq!(opened.metadata, read.metadata);
+        assert_eq!(opened.tables, read.tables);
+        assert_eq!(opened.table("high_frequency").unwrap().datetime.len(), 6000);
+    }
+
+    #[test]
+    fn test_recording_info() {
+        let summary = Recording::info("test_data/cmi/geneactiv.bin").unwrap();
+        let recording =
+            Recording::open("test_data/cmi/geneactiv.bin", ReadOptions::default(), false).unwrap();
+        assert_eq!(summary.format, FileFormat::GeneactivBin);
+        assert_eq!(summary.metadata, recording.metadata);
+        assert_eq!(
+            summary.info.first_timestamp,
+            recording
+                .table("high_frequency")
+                .unwrap()
+                .datetime
+                .first()
+                .copied()
+        );
+    }
+
+    #[test]
+    fn test_reader_with_options() {
+        let data = std::fs::read("test_data/cmi/geneactiv.bin").unwrap();
+        let mut tables = Vec::new();
+        GeneActivReader::with_options(ReadOptions {
+            sensors: SensorSet::from_iter([SensorKind::Temperature]),
+            ..ReadOptions::default()
+        })
+        .read(
+            Cursor::new(data),
+            |_| {},
+            |chunk| tables.extend(chunk),
+            false,
+        )
+        .unwrap();
+        let names: Vec<_> = tables.iter().map(|table| table.name).collect();
+        assert_eq!(names, ["low_frequency"]);
+    }
+
+    #[test]
+    fn test_recording_unknown_format() {
+        let result = Recording::read(
+            Cursor::new(b"\x00\x01\x02\x03\x04"),
+            ReadOptions::default(),
+            false,
+        );
+        assert!(matches!(result, Err(ActfastError::UnknownFormat { .. })));
+    }
 }
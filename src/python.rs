@@ -0,0 +1,692 @@
+//! Python bindings (enabled with the `python` feature)
+
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::sync::Mutex;
+
+use numpy::{PyArray1, prelude::*};
+use pyo3::exceptions::{PyOSError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::pybacked::PyBackedBytes;
+use pyo3::types::{PyByteArray, PyBytes, PyDict, PyList, PyMemoryView, PyTzInfo};
+
+use crate::error::{self, ActfastError};
+use crate::file_format::FileFormat;
+use crate::sensors::MetadataValue;
+use crate::{sensors, stream};
+
+/// Python exceptions mirroring the `ActfastError` variants
+mod exceptions {
+    use pyo3::create_exception;
+    use pyo3::exceptions::PyValueError;
+
+    create_exception!(
+        actfast,
+        ActfastError,
+        PyValueError,
+        "Base class for errors in the contents of an actigraphy file."
+    );
+    create_exception!(
+        actfast,
+        UnknownFormatError,
+        ActfastError,
+        "The file format could not be identified."
+    );
+    create_exception!(
+        actfast,
+        UnsupportedFormatError,
+        ActfastError,
+        "The file format is recognized but cannot be read."
+    );
+    create_exception!(
+        actfast,
+        ParseError,
+        ActfastError,
+        "The file could not be parsed."
+    );
+    create_exception!(
+        actfast,
+        InvalidFieldError,
+        ActfastError,
+        "A field holds an invalid value."
+    );
+    create_exception!(
+        actfast,
+        UnexpectedEofError,
+        ActfastError,
+        "The file ended unexpectedly."
+    );
+    create_exception!(
+        actfast,
+        InvalidDateTimeError,
+        ActfastError,
+        "A date/time could not be parsed."
+    );
+    create_exception!(
+        actfast,
+        InvalidHexError,
+        ActfastError,
+        "Hex-encoded data could not be decoded."
+    );
+    create_exception!(
+        actfast,
+        BitReadError,
+        ActfastError,
+        "Packed binary sample data could not be read."
+    );
+}
+
+// Conversion to PyErr. IO errors become `OSError` (or the exception raised by
+// a Python file-like object), everything else an `actfast.ActfastError`.
+impl From<ActfastError> for PyErr {
+    fn from(err: ActfastError) -> PyErr {
+        let message = err.to_string();
+        let py_err = match &err {
+            ActfastError::Io { source, .. } => {
+                if source.get_ref().is_some_and(|inner| inner.is::<PyErr>()) {
+                    let ActfastError::Io { source, .. } = err else {
+                        unreachable!()
+                    };
+                    return PyErr::from(source);
+                }
+                // `OSError(errno, strerror)` picks the matching subclass,
+                // such as `FileNotFoundError`.
+                return match source.raw_os_error() {
+                    Some(errno) => PyOSError::new_err((errno, message)),
+                    None => PyOSError::new_err(message),
+                };
+            }
+            ActfastError::UnknownFormat { .. } => exceptions::UnknownFormatError::new_err(message),
+            ActfastError::UnsupportedFormat { .. } => {
+                exceptions::UnsupportedFormatError::new_err(message)
+            }
+            ActfastError::Parse { .. } => exceptions::ParseError::new_err(message),
+            ActfastError::InvalidField { .. } => exceptions::InvalidFieldError::new_err(message),
+            ActfastError::UnexpectedEof { .. } => exceptions::UnexpectedEofError::new_err(message),
+            ActfastError::InvalidDateTime { .. } => {
+                exceptions::InvalidDateTimeError::new_err(message)
+            }
+            ActfastError::InvalidHex { .. } => exceptions::InvalidHexError::new_err(message),
+            ActfastError::BitRead { .. } => exceptions::BitReadError::new_err(message),
+        };
+
+        let location = err.location().cloned().unwrap_or_default();
+        Python::attach(|py| {
+            let value = py_err.value(py);
+            let attributes = [
+                ("byte_offset", location.byte_offset),
+                ("record_index", location.record_index.map(|i| i as u64)),
+                ("sample_index", location.sample_index.map(|i| i as u64)),
+                ("line_number", location.line_number.map(|i| i as u64)),
+            ];
+            for (name, attribute) in attributes {
+                // Setting an attribute on a fresh exception instance cannot fail.
+                let _ = value.setattr(name, attribute);
+            }
+        });
+        py_err
+    }
+}
+
+/// Hand a Vec over to numpy without copying, reshaping (as a view) for
+/// multi-axis sensors
+fn sensor_data_to_pyarray<'py, T>(
+    py: Python<'py>,
+    data: Vec<T>,
+    reference_len: usize,
+) -> PyResult<Bound<'py, PyAny>>
+where
+    T: numpy::Element,
+{
+    let len = data.len();
+    let arr = PyArray1::from_vec(py, data);
+    if reference_len == 0 || len == reference_len {
+        return Ok(arr.into_any());
+    }
+
+    let num_channels = len / reference_len;
+    Ok(arr.reshape([reference_len, num_channels])?.into_any())
+}
+
+/// Convert SensorDataDyn to numpy array using a macro to reduce repetition
+macro_rules! sensor_data_dyn_to_pyarray {
+    ($py:expr, $data:expr, $ref_len:expr) => {
+        match $data {
+            sensors::SensorDataDyn::F32(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::F64(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::U8(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::U16(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::U32(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::U64(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::I8(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::I16(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::I32(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::I64(d) => sensor_data_to_pyarray($py, d, $ref_len),
+            sensors::SensorDataDyn::Bool(d) => sensor_data_to_pyarray($py, d, $ref_len),
+        }
+    };
+}
+
+/// Adapts a Python binary file-like object (with `read` and `seek`) to `Read + Seek`
+struct PyFileLike {
+    inner: Py<PyAny>,
+}
+
+impl Read for PyFileLike {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Python::attach(|py| {
+            let data = self.inner.bind(py).call_method1("read", (buf.len(),))?;
+            // `read` may return any bytes-like object, such as a bytearray
+            // or memoryview.
+            let data: PyBackedBytes = match data.extract() {
+                Ok(data) => data,
+                Err(_) => PyMemoryView::from(&data)?
+                    .call_method0("tobytes")?
+                    .extract()
+                    .map_err(PyErr::from)?,
+            };
+            if data.len() > buf.len() {
+                return Err(std::io::Error::other(format!(
+                    "read() returned {} bytes, more than the {} requested",
+                    data.len(),
+                    buf.len()
+                )));
+            }
+            buf[..data.len()].copy_from_slice(&data);
+            Ok(data.len())
+        })
+    }
+}
+
+impl Seek for PyFileLike {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+        Python::attach(|py| {
+            Ok(self
+                .inner
+                .bind(py)
+                .call_method1("seek", (offset, whence))?
+                .extract::<u64>()?)
+        })
+    }
+}
+
+/// Where `read()` takes its data from
+enum Source {
+    Path(std::path::PathBuf),
+    Bytes(PyBackedBytes),
+    FileLike(Py<PyAny>),
+}
+
+impl Source {
+    fn extract(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+        // Checked before paths, since `os.fspath` also accepts bytes.
+        if value.is_instance_of::<PyBytes>() || value.is_instance_of::<PyByteArray>() {
+            return Ok(Source::Bytes(value.extract()?));
+        }
+        if let Ok(path) = value.extract() {
+            return Ok(Source::Path(path));
+        }
+        if value.hasattr("read")? && value.hasattr("seek")? {
+            return Ok(Source::FileLike(value.clone().unbind()));
+        }
+        Err(PyTypeError::new_err(format!(
+            "expected a path, bytes or a binary file-like object, got '{}'",
+            value.get_type().name()?
+        )))
+    }
+
+    /// Open the source and identify its format
+    fn open(self) -> error::Result<(FileFormat, SourceReader)> {
+        match self {
+            Source::Path(path) => {
+                let (format_type, file) = crate::open_and_identify(&path)?;
+                Ok((format_type, SourceReader::File(file)))
+            }
+            Source::Bytes(bytes) => {
+                let mut cursor = Cursor::new(bytes);
+                let format_type = crate::identify(&mut cursor)?;
+                Ok((format_type, SourceReader::Bytes(cursor)))
+            }
+            Source::FileLike(file) => {
+                let mut reader = BufReader::new(PyFileLike { inner: file });
+                let format_type = crate::identify(&mut reader)?;
+                Ok((format_type, SourceReader::FileLike(reader)))
+            }
+        }
+    }
+}
+
+/// An opened `Source`
+enum SourceReader {
+    File(std::fs::File),
+    Bytes(Cursor<PyBackedBytes>),
+    FileLike(BufReader<PyFileLike>),
+}
+
+impl Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            SourceReader::File(file) => file.read(buf),
+            SourceReader::Bytes(cursor) => cursor.read(buf),
+            SourceReader::FileLike(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for SourceReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            SourceReader::File(file) => file.seek(pos),
+            SourceReader::Bytes(cursor) => cursor.seek(pos),
+            SourceReader::FileLike(reader) => reader.seek(pos),
+        }
+    }
+}
+
+/// Insert a metadata entry into a `{category: {key: value}}` dict
+fn insert_metadata<S: AsRef<str>>(
+    dict_metadata: &Bound<'_, PyDict>,
+    category: &str,
+    key: &str,
+    value: &MetadataValue<S>,
+) -> PyResult<()> {
+    let category_dict = match dict_metadata.get_item(category)? {
+        Some(item) => item.cast_into::<PyDict>()?,
+        None => {
+            let d = PyDict::new(dict_metadata.py());
+            dict_metadata.set_item(category, &d)?;
+            d
+        }
+    };
+    match value {
+        MetadataValue::Int(v) => category_dict.set_item(key, v),
+        MetadataValue::Float(v) => category_dict.set_item(key, v),
+        MetadataValue::Bool(v) => category_dict.set_item(key, v),
+        MetadataValue::DateTime(v) => category_dict.set_item(key, v),
+        MetadataValue::String(v) => category_dict.set_item(key, v.as_ref()),
+    }
+}
+
+/// Convert metadata entries to a `{category: {key: value}}` dict
+fn metadata_to_pydict<'py>(
+    py: Python<'py>,
+    metadata: &[sensors::OwnedMetadataEntry],
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict_metadata = PyDict::new(py);
+    for entry in metadata {
+        insert_metadata(&dict_metadata, &entry.category, &entry.key, &entry.value)?;
+    }
+    Ok(dict_metadata)
+}
+
+/// Convert events to a list of `(time, kind, payload)` tuples
+fn events_to_pylist<'py>(
+    py: Python<'py>,
+    events: &[sensors::Event],
+) -> PyResult<Bound<'py, PyList>> {
+    PyList::new(
+        py,
+        events
+            .iter()
+            .map(|event| (event.time, event.kind, PyBytes::new(py, &event.payload))),
+    )
+}
+
+/// Convert segments to a list of dicts
+fn segments_to_pylist<'py>(
+    py: Python<'py>,
+    segments: &[sensors::Segment],
+) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+    for segment in segments {
+        let dict = PyDict::new(py);
+        dict.set_item("start_sector", segment.start_sector)?;
+        dict.set_item("start_time", segment.start_time)?;
+        dict.set_item("sample_rate", segment.sample_rate)?;
+        dict.set_item("accelerometer_range", segment.accelerometer_range)?;
+        dict.set_item("gyroscope_range", segment.gyroscope_range)?;
+        dict.set_item("axes", segment.axes)?;
+        list.append(dict)?;
+    }
+    Ok(list)
+}
+
+/// Convert a sensor table to a dict of numpy arrays, moving its buffers into numpy
+fn sensor_table_to_pydict(
+    py: Python<'_>,
+    sensor_table: sensors::SensorTable,
+) -> PyResult<Bound<'_, PyDict>> {
+    let dict_sensor_table = PyDict::new(py);
+    let reference_len = sensor_table.datetime.len();
+    let np_datetime = PyArray1::from_vec(py, sensor_table.datetime);
+    dict_sensor_table.set_item("datetime", np_datetime)?;
+
+    for sensor_data in sensor_table.data {
+        let key = sensor_data.kind.as_str();
+        let np_array = sensor_data_dyn_to_pyarray!(py, sensor_data.data, reference_len)?;
+        dict_sensor_table.set_item(key, np_array)?;
+    }
+    Ok(dict_sensor_table)
+}
+
+/// Convert a `start`/`end` argument (int nanoseconds or datetime) to nanoseconds
+/// since the Unix epoch. Naive datetimes are taken as UTC, like the returned
+/// timestamps.
+fn extract_time_bound(value: &Bound<'_, PyAny>) -> PyResult<i64> {
+    if let Ok(nanos) = value.extract::<i64>() {
+        return Ok(nanos);
+    }
+    let datetime = match value.extract::<chrono::NaiveDateTime>() {
+        Ok(naive) => naive.and_utc(),
+        Err(_) => value
+            .call_method1("astimezone", (PyTzInfo::utc(value.py())?,))?
+            .extract::<chrono::DateTime<chrono::Utc>>()?,
+    };
+    datetime.timestamp_nanos_opt().ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "datetime {} is out of the nanosecond timestamp range",
+            datetime
+        ))
+    })
+}
+
+/// Convert a `sensors` argument (list of sensor names) to a sensor set
+fn extract_sensor_set(names: Option<Vec<String>>) -> PyResult<sensors::SensorSet> {
+    let Some(names) = names else {
+        return Ok(sensors::SensorSet::ALL);
+    };
+    names
+        .iter()
+        .map(|name| {
+            sensors::SensorKind::from_name(name).ok_or_else(|| {
+                let valid: Vec<&str> = sensors::SensorKind::ALL
+                    .iter()
+                    .map(|kind| kind.as_str())
+                    .collect();
+                PyValueError::new_err(format!(
+                    "unknown sensor '{}', expected one of: {}",
+                    name,
+                    valid.join(", ")
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Look up an idle sleep mode by name
+fn extract_idle_sleep(name: &str) -> PyResult<sensors::IdleSleep> {
+    sensors::IdleSleep::from_name(name).ok_or_else(|| {
+        let valid: Vec<&str> = sensors::IdleSleep::ALL
+            .iter()
+            .map(|mode| mode.as_str())
+            .collect();
+        PyValueError::new_err(format!(
+            "unknown idle_sleep mode '{}', expected one of: {}",
+            name,
+            valid.join(", ")
+        ))
+    })
+}
+
+/// Look up an Axivity timestamp mode by name
+fn extract_timestamps(name: &str) -> PyResult<sensors::Timestamps> {
+    sensors::Timestamps::from_name(name).ok_or_else(|| {
+        let valid: Vec<&str> = sensors::Timestamps::ALL
+            .iter()
+            .map(|mode| mode.as_str())
+            .collect();
+        PyValueError::new_err(format!(
+            "unknown timestamps mode '{}', expected one of: {}",
+            name,
+            valid.join(", ")
+        ))
+    })
+}
+
+/// Build read options for a `[start, end)` window, sensor selection, idle
+/// sleep mode and timestamp mode
+fn read_options(
+    start: Option<&Bound<'_, PyAny>>,
+    end: Option<&Bound<'_, PyAny>>,
+    sensors: Option<Vec<String>>,
+    idle_sleep: &str,
+    timestamps: &str,
+) -> PyResult<sensors::ReadOptions> {
+    Ok(sensors::ReadOptions {
+        start: start.map(extract_time_bound).transpose()?,
+        end: end.map(extract_time_bound).transpose()?,
+        sensors: extract_sensor_set(sensors)?,
+        idle_sleep: extract_idle_sleep(idle_sleep)?,
+        timestamps: extract_timestamps(timestamps)?,
+        ..sensors::ReadOptions::default()
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (path, lenient=false, start=None, end=None, sensors=None, idle_sleep="leave", timestamps="block"))]
+#[allow(clippy::too_many_arguments)]
+fn read(
+    py: Python,
+    path: &Bound<'_, PyAny>,
+    lenient: bool,
+    start: Option<&Bound<'_, PyAny>>,
+    end: Option<&Bound<'_, PyAny>>,
+    sensors: Option<Vec<String>>,
+    idle_sleep: &str,
+    timestamps: &str,
+) -> PyResult<Py<PyAny>> {
+    let options = read_options(start, end, sensors, idle_sleep, timestamps)?;
+    let source = Source::extract(path)?;
+
+    // Decode without the GIL, only the conversion below needs Python objects.
+    let recording = py.detach(|| {
+        let (format_type, reader) = source.open()?;
+        crate::Recording::read_format(format_type, reader, options, lenient)
+    })?;
+
+    let dict_metadata = metadata_to_pydict(py, &recording.metadata)?;
+    let dict_timeseries = PyDict::new(py);
+    for sensor_table in recording.tables {
+        dict_timeseries.set_item(sensor_table.name, sensor_table_to_pydict(py, sensor_table)?)?;
+    }
+
+    let dict = PyDict::new(py);
+    dict.set_item("format", recording.format.to_string())?;
+    dict.set_item("timeseries", dict_timeseries)?;
+    dict.set_item("metadata", dict_metadata)?;
+
+    // Add warnings if any
+    let warnings_list = PyList::new(py, &recording.warnings)?;
+    dict.set_item("warnings", warnings_list)?;
+    dict.set_item("filled_spans", PyList::new(py, &recording.filled_spans)?)?;
+    dict.set_item("events", events_to_pylist(py, &recording.events)?)?;
+    dict.set_item("checksum_mismatches", recording.checksum_mismatches)?;
+    dict.set_item("effective_sample_rate", recording.effective_sample_rate)?;
+    dict.set_item("segments", segments_to_pylist(py, &recording.segments)?)?;
+
+    Ok(dict.into())
+}
+
+#[pyfunction]
+fn info(py: Python, path: &Bound<'_, PyAny>) -> PyResult<Py<PyAny>> {
+    let source = Source::extract(path)?;
+
+    let summary = py.detach(|| {
+        let (format_type, reader) = source.open()?;
+        crate::RecordingSummary::read_format(format_type, reader)
+    })?;
+    let recording_info = summary.info;
+
+    let dict = PyDict::new(py);
+    dict.set_item("format", summary.format.to_string())?;
+    dict.set_item("metadata", metadata_to_pydict(py, &summary.metadata)?)?;
+    dict.set_item("first_timestamp", recording_info.first_timestamp)?;
+    dict.set_item("last_timestamp", recording_info.last_timestamp)?;
+    dict.set_item("sample_rate", recording_info.sample_rate)?;
+    dict.set_item("estimated_samples", recording_info.estimated_samples)?;
+    let sensor_names: Vec<&str> = recording_info
+        .sensors
+        .iter()
+        .map(|kind| kind.as_str())
+        .collect();
+    dict.set_item("sensors", PyList::new(py, sensor_names)?)?;
+
+    Ok(dict.into())
+}
+
+/// An actigraphy file opened for chunked reading
+#[pyclass(frozen, name = "File")]
+struct PyFile {
+    path: std::path::PathBuf,
+    format: FileFormat,
+}
+
+#[pymethods]
+impl PyFile {
+    #[getter]
+    fn format(&self) -> String {
+        self.format.to_string()
+    }
+
+    #[pyo3(signature = (samples=stream::DEFAULT_CHUNK_SAMPLES, lenient=false, start=None, end=None, sensors=None, idle_sleep="leave", timestamps="block"))]
+    #[allow(clippy::too_many_arguments)]
+    fn iter_chunks(
+        &self,
+        samples: usize,
+        lenient: bool,
+        start: Option<&Bound<'_, PyAny>>,
+        end: Option<&Bound<'_, PyAny>>,
+        sensors: Option<Vec<String>>,
+        idle_sleep: &str,
+        timestamps: &str,
+    ) -> PyResult<PyChunkIterator> {
+        let options = sensors::ReadOptions {
+            chunk_samples: Some(samples),
+            ..read_options(start, end, sensors, idle_sleep, timestamps)?
+        };
+        let iterator = stream::ChunkIterator::open(&self.path, options, lenient)?;
+        Ok(PyChunkIterator {
+            inner: Mutex::new(iterator),
+        })
+    }
+}
+
+/// Iterator over the chunks of a file, see `File.iter_chunks`
+#[pyclass(frozen, name = "ChunkIterator")]
+struct PyChunkIterator {
+    inner: Mutex<stream::ChunkIterator>,
+}
+
+#[pymethods]
+impl PyChunkIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Option<Py<PyAny>>> {
+        // Decoding happens on the worker thread, don't block it on the GIL.
+        let chunk = py.detach(|| self.inner.lock().unwrap().next());
+        let Some(chunk) = chunk.transpose()? else {
+            return Ok(None);
+        };
+
+        let dict_timeseries = PyDict::new(py);
+        for sensor_table in chunk.tables {
+            dict_timeseries
+                .set_item(sensor_table.name, sensor_table_to_pydict(py, sensor_table)?)?;
+        }
+        let dict = PyDict::new(py);
+        dict.set_item("timeseries", dict_timeseries)?;
+        Ok(Some(dict.into()))
+    }
+
+    #[getter]
+    fn format(&self) -> String {
+        self.inner.lock().unwrap().format().to_string()
+    }
+
+    #[getter]
+    fn metadata(&self, py: Python) -> PyResult<Py<PyAny>> {
+        Ok(metadata_to_pydict(py, self.inner.lock().unwrap().metadata())?.into())
+    }
+
+    #[getter]
+    fn warnings(&self, py: Python) -> PyResult<Py<PyAny>> {
+        Ok(PyList::new(py, self.inner.lock().unwrap().warnings())?.into())
+    }
+
+    #[getter]
+    fn filled_spans(&self, py: Python) -> PyResult<Py<PyAny>> {
+        Ok(PyList::new(py, self.inner.lock().unwrap().filled_spans())?.into())
+    }
+
+    #[getter]
+    fn events(&self, py: Python) -> PyResult<Py<PyAny>> {
+        Ok(events_to_pylist(py, self.inner.lock().unwrap().events())?.into())
+    }
+
+    #[getter]
+    fn checksum_mismatches(&self) -> usize {
+        self.inner.lock().unwrap().checksum_mismatches()
+    }
+
+    #[getter]
+    fn effective_sample_rate(&self) -> Option<f64> {
+        self.inner.lock().unwrap().effective_sample_rate()
+    }
+
+    #[getter]
+    fn segments(&self, py: Python) -> PyResult<Py<PyAny>> {
+        Ok(segments_to_pylist(py, self.inner.lock().unwrap().segments())?.into())
+    }
+}
+
+#[pyfunction]
+fn open(py: Python, path: std::path::PathBuf) -> PyResult<PyFile> {
+    let (format, _) = py.detach(|| crate::open_and_identify(&path))?;
+    crate::check_supported(format)?;
+    Ok(PyFile { path, format })
+}
+
+#[pymodule]
+fn actfast(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(read, m)?)?;
+    m.add_function(wrap_pyfunction!(info, m)?)?;
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_class::<PyFile>()?;
+    m.add_class::<PyChunkIterator>()?;
+
+    let py = m.py();
+    m.add("ActfastError", py.get_type::<exceptions::ActfastError>())?;
+    m.add(
+        "UnknownFormatError",
+        py.get_type::<exceptions::UnknownFormatError>(),
+    )?;
+    m.add(
+        "UnsupportedFormatError",
+        py.get_type::<exceptions::UnsupportedFormatError>(),
+    )?;
+    m.add("ParseError", py.get_type::<exceptions::ParseError>())?;
+    m.add(
+        "InvalidFieldError",
+        py.get_type::<exceptions::InvalidFieldError>(),
+    )?;
+    m.add(
+        "UnexpectedEofError",
+        py.get_type::<exceptions::UnexpectedEofError>(),
+    )?;
+    m.add(
+        "InvalidDateTimeError",
+        py.get_type::<exceptions::InvalidDateTimeError>(),
+    )?;
+    m.add(
+        "InvalidHexError",
+        py.get_type::<exceptions::InvalidHexError>(),
+    )?;
+    m.add("BitReadError", py.get_type::<exceptions::BitReadError>())?;
+    Ok(())
+}
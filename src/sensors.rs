@@ -1,78 +1,492 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Utc};
+
 use crate::error::Result;
 
+/// A typed metadata value
+///
+/// Readers parse numbers, flags and timestamps they know the meaning of and
+/// fall back to [`MetadataValue::String`] for everything else (or values that
+/// fail to parse). Timestamps stored as local time in the file are taken as
+/// UTC, like sample timestamps. `S` is `&str` in callbacks and `String` once
+/// owned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetadataValue<S = String> {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    DateTime(DateTime<Utc>),
+    String(S),
+}
+
+impl<'a> MetadataValue<&'a str> {
+    /// Parse an integer (ignoring surrounding whitespace), keeping the raw string otherwise
+    pub fn int_or_str(value: &'a str) -> Self {
+        value
+            .trim()
+            .parse()
+            .map_or(MetadataValue::String(value), MetadataValue::Int)
+    }
+
+    /// Parse a float (ignoring surrounding whitespace), keeping the raw string otherwise
+    pub fn float_or_str(value: &'a str) -> Self {
+        value
+            .trim()
+            .parse()
+            .map_or(MetadataValue::String(value), MetadataValue::Float)
+    }
+
+    pub fn into_owned(self) -> MetadataValue {
+        match self {
+            MetadataValue::Int(v) => MetadataValue::Int(v),
+            MetadataValue::Float(v) => MetadataValue::Float(v),
+            MetadataValue::Bool(v) => MetadataValue::Bool(v),
+            MetadataValue::DateTime(v) => MetadataValue::DateTime(v),
+            MetadataValue::String(v) => MetadataValue::String(v.to_owned()),
+        }
+    }
+}
+
+impl MetadataValue {
+    /// Borrow the string of an owned value, for passing it to a callback
+    pub fn as_deref(&self) -> MetadataValue<&str> {
+        match self {
+            MetadataValue::Int(v) => MetadataValue::Int(*v),
+            MetadataValue::Float(v) => MetadataValue::Float(*v),
+            MetadataValue::Bool(v) => MetadataValue::Bool(*v),
+            MetadataValue::DateTime(v) => MetadataValue::DateTime(*v),
+            MetadataValue::String(v) => MetadataValue::String(v),
+        }
+    }
+}
+
+impl<S: AsRef<str>> fmt::Display for MetadataValue<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataValue::Int(v) => write!(f, "{}", v),
+            MetadataValue::Float(v) => write!(f, "{}", v),
+            MetadataValue::Bool(v) => write!(f, "{}", v),
+            MetadataValue::DateTime(v) => write!(f, "{}", v.to_rfc3339()),
+            MetadataValue::String(v) => write!(f, "{}", v.as_ref()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct MetadataEntry<'a> {
     pub category: &'a str,
     pub key: &'a str,
-    pub value: &'a str,
+    pub value: MetadataValue<&'a str>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Owned copy of a [`MetadataEntry`], for holding on to metadata after the callback
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedMetadataEntry {
+    pub category: String,
+    pub key: String,
+    pub value: MetadataValue,
+}
+
+impl From<MetadataEntry<'_>> for OwnedMetadataEntry {
+    fn from(entry: MetadataEntry<'_>) -> Self {
+        Self {
+            category: entry.category.to_owned(),
+            key: entry.key.to_owned(),
+            value: entry.value.into_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SensorKind {
     Accelerometer,
     Gyroscope,
+    Magnetometer,
+    /// Uncalibrated magnetometer readings in sensor counts
+    MagnetometerCounts,
     Light,
     ButtonState,
     Capacitive,
     Temperature,
     BatteryVoltage,
+    ActivityCounts,
+    Steps,
+    Inclinometer,
+    HeartRate,
+    RrInterval,
 }
 
 impl SensorKind {
+    pub const ALL: [SensorKind; 14] = [
+        SensorKind::Accelerometer,
+        SensorKind::Gyroscope,
+        SensorKind::Magnetometer,
+        SensorKind::MagnetometerCounts,
+        SensorKind::Light,
+        SensorKind::ButtonState,
+        SensorKind::Capacitive,
+        SensorKind::Temperature,
+        SensorKind::BatteryVoltage,
+        SensorKind::ActivityCounts,
+        SensorKind::Steps,
+        SensorKind::Inclinometer,
+        SensorKind::HeartRate,
+        SensorKind::RrInterval,
+    ];
+
+    /// Look up a sensor kind by its [`as_str`](Self::as_str) name.
+    pub fn from_name(name: &str) -> Option<SensorKind> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             SensorKind::Accelerometer => "acceleration",
             SensorKind::Gyroscope => "gyroscope",
+            SensorKind::Magnetometer => "magnetometer",
+            SensorKind::MagnetometerCounts => "magnetometer_counts",
             SensorKind::Light => "light",
             SensorKind::ButtonState => "button_state",
             SensorKind::Capacitive => "capsense",
             SensorKind::Temperature => "temperature",
             SensorKind::BatteryVoltage => "battery_voltage",
+            SensorKind::ActivityCounts => "counts",
+            SensorKind::Steps => "steps",
+            SensorKind::Inclinometer => "inclinometer",
+            SensorKind::HeartRate => "heart_rate",
+            SensorKind::RrInterval => "rr_interval",
         }
     }
 }
 
-#[allow(dead_code)]
-pub enum SensorDataDyn<'a> {
-    F32(&'a [f32]),
-    F64(&'a [f64]),
-    U8(&'a [u8]),
-    U16(&'a [u16]),
-    U32(&'a [u32]),
-    U64(&'a [u64]),
-    I8(&'a [i8]),
-    I16(&'a [i16]),
-    I32(&'a [i32]),
-    I64(&'a [i64]),
-    Bool(&'a [bool]),
+/// A set of sensor kinds, used to select which sensors a reader decodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorSet(u32);
+
+impl SensorSet {
+    pub const ALL: SensorSet = SensorSet(u32::MAX);
+    pub const EMPTY: SensorSet = SensorSet(0);
+
+    pub fn insert(&mut self, kind: SensorKind) {
+        self.0 |= 1 << kind as u32;
+    }
+
+    pub fn contains(&self, kind: SensorKind) -> bool {
+        self.0 & (1 << kind as u32) != 0
+    }
+
+    /// Returns `true` if any of `kinds` is in the set.
+    pub fn contains_any(&self, kinds: &[SensorKind]) -> bool {
+        kinds.iter().any(|&kind| self.contains(kind))
+    }
 }
 
-pub struct SensorData<'a> {
+impl Default for SensorSet {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl FromIterator<SensorKind> for SensorSet {
+    fn from_iter<I: IntoIterator<Item = SensorKind>>(iter: I) -> Self {
+        let mut set = Self::EMPTY;
+        for kind in iter {
+            set.insert(kind);
+        }
+        set
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SensorDataDyn {
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+    I8(Vec<i8>),
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    Bool(Vec<bool>),
+}
+
+impl SensorDataDyn {
+    /// Number of values, i.e. samples times channels
+    pub fn len(&self) -> usize {
+        match self {
+            SensorDataDyn::F32(v) => v.len(),
+            SensorDataDyn::F64(v) => v.len(),
+            SensorDataDyn::U8(v) => v.len(),
+            SensorDataDyn::U16(v) => v.len(),
+            SensorDataDyn::U32(v) => v.len(),
+            SensorDataDyn::U64(v) => v.len(),
+            SensorDataDyn::I8(v) => v.len(),
+            SensorDataDyn::I16(v) => v.len(),
+            SensorDataDyn::I32(v) => v.len(),
+            SensorDataDyn::I64(v) => v.len(),
+            SensorDataDyn::Bool(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorData {
     pub kind: SensorKind,
-    pub data: SensorDataDyn<'a>,
+    pub data: SensorDataDyn,
 }
 
-pub struct SensorTable<'a> {
-    pub name: &'a str,
-    pub datetime: &'a [i64],
-    pub data: Vec<SensorData<'a>>,
+/// A sensor table handed over by a reader.
+///
+/// Readers move their buffers into the table, so each table owns its data.
+/// In chunked mode a reader hands over the tables of each chunk together,
+/// see [`read_format`](crate::read_format).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorTable {
+    pub name: &'static str,
+    pub datetime: Vec<i64>,
+    pub data: Vec<SensorData>,
+}
+
+impl SensorTable {
+    pub fn is_empty(&self) -> bool {
+        self.datetime.is_empty()
+    }
+
+    /// Drop the columns not in `sensors`. Returns `None` if no column is left.
+    pub fn select(mut self, sensors: SensorSet) -> Option<SensorTable> {
+        self.data.retain(|d| sensors.contains(d.kind));
+        (!self.data.is_empty()).then_some(self)
+    }
+}
+
+/// How to treat the seconds an ActiGraph device spent in idle sleep mode,
+/// during which it logs no (or empty) activity records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdleSleep {
+    /// Leave the idle seconds out, so the timestamps have gaps
+    #[default]
+    Leave,
+    /// Repeat the last sample before the gap, like ActiLife
+    FillLast,
+    /// Fill the idle seconds with NaN samples
+    Nan,
+}
+
+impl IdleSleep {
+    pub const ALL: [IdleSleep; 3] = [IdleSleep::Leave, IdleSleep::FillLast, IdleSleep::Nan];
+
+    /// Look up a mode by its [`as_str`](Self::as_str) name.
+    pub fn from_name(name: &str) -> Option<IdleSleep> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == name)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdleSleep::Leave => "leave",
+            IdleSleep::FillLast => "fill_last",
+            IdleSleep::Nan => "nan",
+        }
+    }
+}
+
+/// How to time the samples of an Axivity data sector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Timestamps {
+    /// Space the samples at the nominal rate from the sector's own block
+    /// timestamp, which can leave jitter, overlaps and gaps between sectors
+    #[default]
+    Block,
+    /// Space the samples evenly between consecutive block timestamps, like
+    /// the OpenMovement tools and GGIRread
+    Interpolated,
+}
+
+impl Timestamps {
+    pub const ALL: [Timestamps; 2] = [Timestamps::Block, Timestamps::Interpolated];
+
+    /// Look up a mode by its [`as_str`](Self::as_str) name.
+    pub fn from_name(name: &str) -> Option<Timestamps> {
+        Self::ALL.into_iter().find(|mode| mode.as_str() == name)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Timestamps::Block => "block",
+            Timestamps::Interpolated => "interpolated",
+        }
+    }
+}
+
+/// Options shared by all format readers
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// Emit sensor tables in chunks once the primary (highest rate) table holds
+    /// at least this many samples, instead of once at the end of the file.
+    /// Chunks end on record boundaries, so they can be slightly larger.
+    pub chunk_samples: Option<usize>,
+    /// Only return samples at or after this time (nanoseconds since the Unix epoch).
+    pub start: Option<i64>,
+    /// Only return samples before this time (nanoseconds since the Unix epoch).
+    pub end: Option<i64>,
+    /// Sensors to decode. Readers skip unselected channels and leave out
+    /// tables with no selected channels.
+    pub sensors: SensorSet,
+    /// Reconstruction of idle sleep gaps (ActiGraph `log.bin` files only).
+    pub idle_sleep: IdleSleep,
+    /// Timing of the samples within a data sector (Axivity only).
+    pub timestamps: Timestamps,
+    /// Set by the consumer to stop a chunked read early. Readers check it
+    /// after each chunk and return what they have read so far.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl ReadOptions {
+    /// Returns `true` if `samples` buffered samples should be flushed as a chunk.
+    pub fn chunk_full(&self, samples: usize) -> bool {
+        self.chunk_samples.is_some_and(|n| samples >= n)
+    }
+
+    /// Returns `true` if the consumer asked to stop reading.
+    pub fn cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Returns `true` if a sample at `time` lies inside the `[start, end)` window.
+    pub fn in_window(&self, time: i64) -> bool {
+        !self.before_window(time) && !self.after_window(time)
+    }
+
+    /// Returns `true` if `time` is before the start of the window.
+    pub fn before_window(&self, time: i64) -> bool {
+        self.start.is_some_and(|start| time < start)
+    }
+
+    /// Returns `true` if `time` is at or after the end of the window.
+    pub fn after_window(&self, time: i64) -> bool {
+        self.end.is_some_and(|end| time >= end)
+    }
+
+    /// Returns `true` if the span `[from, to)` overlaps the window.
+    pub fn overlaps_window(&self, from: i64, to: i64) -> bool {
+        self.start.is_none_or(|start| to > start) && self.end.is_none_or(|end| from < end)
+    }
+
+    /// Clamp a pre-allocation estimate to the chunk size, if chunking.
+    pub fn reserve_limit(&self, samples: usize) -> usize {
+        self.chunk_samples.map_or(samples, |n| samples.min(n))
+    }
+}
+
+/// A device event or diagnostic record, such as an ActiGraph idle sleep
+/// transition or FIFO error
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Time of the event (nanoseconds since the Unix epoch).
+    pub time: i64,
+    /// Kind of record, such as `"event"` or `"fifo_error"` (format specific).
+    pub kind: &'static str,
+    /// Undecoded record payload.
+    pub payload: Vec<u8>,
+}
+
+/// A run of Axivity data sectors recorded with the same configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Index of the first data sector of the run.
+    pub start_sector: usize,
+    /// Time of the first sample of the run (nanoseconds since the Unix epoch).
+    pub start_time: i64,
+    /// Nominal sample rate in Hz.
+    pub sample_rate: f64,
+    /// Accelerometer range in g.
+    pub accelerometer_range: u16,
+    /// Gyroscope range in degrees per second, if the run has a gyroscope.
+    pub gyroscope_range: Option<f32>,
+    /// Axes per sample: 3 (accelerometer), 6 (and gyroscope) or 9 (and
+    /// magnetometer).
+    pub axes: u8,
 }
 
 /// Result of reading a sensor file
 #[derive(Debug, Default)]
 pub struct ReadResult {
-    /// Warnings encountered during parsing (only populated in lenient mode)
+    /// Warnings about the data: parse errors (only in lenient mode) and
+    /// problems reported by the device itself
     pub warnings: Vec<String>,
+    /// Time spans `[start, end)` (nanoseconds since the Unix epoch) filled with
+    /// reconstructed samples, see [`ReadOptions::idle_sleep`]
+    pub filled_spans: Vec<(i64, i64)>,
+    /// Device events inside the time window, in file order
+    pub events: Vec<Event>,
+    /// Number of records or sectors whose checksum did not match (skipped
+    /// in lenient mode)
+    pub checksum_mismatches: usize,
+    /// Sample rate in Hz observed between consecutive block timestamps
+    /// (Axivity only)
+    pub effective_sample_rate: Option<f64>,
+    /// Configuration of each run of data sectors read, in file order
+    /// (Axivity only)
+    pub segments: Vec<Segment>,
 }
 
 impl ReadResult {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Record a filled span, merging it with the previous one if they touch
+    pub fn push_filled_span(&mut self, start: i64, end: i64) {
+        match self.filled_spans.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => self.filled_spans.push((start, end)),
+        }
+    }
+}
+
+/// Summary of a recording, gathered without decoding sample data
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordingInfo {
+    /// Time of the first sample (nanoseconds since the Unix epoch).
+    pub first_timestamp: Option<i64>,
+    /// Time of the last sample (nanoseconds since the Unix epoch).
+    pub last_timestamp: Option<i64>,
+    /// Nominal sample rate of the highest rate sensor in Hz.
+    pub sample_rate: Option<f64>,
+    /// Estimated number of samples of the highest rate sensor.
+    pub estimated_samples: Option<u64>,
+    /// Sensors present in the recording.
+    pub sensors: Vec<SensorKind>,
+}
+
+impl RecordingInfo {
+    /// Estimate the sample count from the first/last timestamps and the sample rate.
+    pub fn estimate_samples(&self) -> Option<u64> {
+        let (first, last) = (self.first_timestamp?, self.last_timestamp?);
+        let sample_rate = self.sample_rate?;
+        let duration_seconds = last.checked_sub(first)? as f64 / 1e9;
+        (duration_seconds >= 0.0).then(|| (duration_seconds * sample_rate) as u64 + 1)
+    }
 }
 
-pub trait SensorsFormatReader<'a> {
+pub trait SensorsFormatReader {
+    /// Decode the samples of `reader`.
+    ///
+    /// `sensor_table_callback` is called once per chunk with the tables
+    /// decoded since the previous call (once in total when not chunking).
+    /// Tables with no selected sensors are left out.
     fn read<R: std::io::Read + std::io::Seek, M, S>(
-        &'a mut self,
+        &mut self,
         reader: R,
         metadata_callback: M,
         sensor_table_callback: S,
@@ -80,5 +494,14 @@ pub trait SensorsFormatReader<'a> {
     ) -> Result<ReadResult>
     where
         M: FnMut(MetadataEntry),
-        S: FnMut(SensorTable<'a>);
+        S: FnMut(Vec<SensorTable>);
+
+    /// Read the metadata and a [`RecordingInfo`] summary, skipping sample data.
+    fn info<R: std::io::Read + std::io::Seek, M>(
+        &mut self,
+        reader: R,
+        metadata_callback: M,
+    ) -> Result<RecordingInfo>
+    where
+        M: FnMut(MetadataEntry);
 }
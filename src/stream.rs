@@ -0,0 +1,319 @@
+//! Chunked (streaming) reading
+//!
+//! The format readers push data through callbacks. To expose that as a
+//! pull-based iterator, the reader runs on a worker thread and hands each
+//! chunk over a bounded channel. The worker blocks until the consumer has
+//! taken the previous chunk, so only a few chunks are ever held in memory.
+//! Dropping the iterator cancels the read at the next chunk boundary.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use crate::error::{ActfastError, Result};
+use crate::file_format::FileFormat;
+use crate::sensors::{Event, OwnedMetadataEntry, ReadOptions, ReadResult, Segment, SensorTable};
+
+/// Default number of samples per chunk
+pub const DEFAULT_CHUNK_SAMPLES: usize = 1_000_000;
+
+/// A time-ordered slice of every sensor table of a recording.
+///
+/// Each chunk holds the samples decoded since the previous chunk; tables that
+/// received no new samples are present but empty. Tables and columns that
+/// only exist if the file has certain records, such as the GT3X `imu`,
+/// `epoch_counts` and `heart_rate` tables, first appear in the chunk that
+/// decodes the first such record, so earlier chunks lack them.
+pub struct Chunk {
+    pub tables: Vec<SensorTable>,
+}
+
+enum Message {
+    Metadata(OwnedMetadataEntry),
+    Chunk(Chunk),
+    Finished(Result<ReadResult>),
+}
+
+/// Iterator over the chunks of a recording.
+///
+/// Metadata is collected as the reader encounters it (usually before the
+/// first chunk). Warnings, filled spans, events, the checksum mismatch count,
+/// the effective sample rate and the segments are available once the
+/// iterator is exhausted.
+pub struct ChunkIterator {
+    format: FileFormat,
+    receiver: mpsc::Receiver<Message>,
+    cancel: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+    metadata: Vec<OwnedMetadataEntry>,
+    warnings: Vec<String>,
+    filled_spans: Vec<(i64, i64)>,
+    events: Vec<Event>,
+    checksum_mismatches: usize,
+    effective_sample_rate: Option<f64>,
+    segments: Vec<Segment>,
+    finished: bool,
+}
+
+impl ChunkIterator {
+    /// Start reading `path` in chunks of roughly `options.chunk_samples` samples
+    /// ([`DEFAULT_CHUNK_SAMPLES`] if unset).
+    pub fn open(path: &Path, mut options: ReadOptions, lenient: bool) -> Result<Self> {
+        let (format, file) = crate::open_and_identify(path)?;
+        crate::check_supported(format)?;
+
+        options.chunk_samples = Some(
+            options
+                .chunk_samples
+                .unwrap_or(DEFAULT_CHUNK_SAMPLES)
+                .max(1),
+        );
+        let cancel = Arc::new(AtomicBool::new(false));
+        options.cancel = Some(cancel.clone());
+        let (sender, receiver) = mpsc::sync_channel(1);
+
+        let worker = std::thread::spawn({
+            let cancel = cancel.clone();
+            move || {
+                // A send error means the consumer hung up, so stop reading.
+                let send = |message| {
+                    if sender.send(message).is_err() {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                };
+                let result = crate::read_format(
+                    format,
+                    file,
+                    options,
+                    |entry| send(Message::Metadata(entry.into())),
+                    |tables: Vec<SensorTable>| {
+                        if tables.iter().any(|t| !t.is_empty()) {
+                            send(Message::Chunk(Chunk { tables }));
+                        }
+                    },
+                    lenient,
+                );
+                send(Message::Finished(result));
+            }
+        });
+
+        Ok(Self {
+            format,
+            receiver,
+            cancel,
+            worker: Some(worker),
+            metadata: Vec::new(),
+            warnings: Vec::new(),
+            filled_spans: Vec::new(),
+            events: Vec::new(),
+            checksum_mismatches: 0,
+            effective_sample_rate: None,
+            segments: Vec::new(),
+            finished: false,
+        })
+    }
+
+    pub fn format(&self) -> FileFormat {
+        self.format
+    }
+
+    /// Metadata encountered so far
+    pub fn metadata(&self) -> &[OwnedMetadataEntry] {
+        &self.metadata
+    }
+
+    /// Warnings (only complete once the iterator is exhausted)
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Spans filled with reconstructed samples (only complete once the
+    /// iterator is exhausted)
+    pub fn filled_spans(&self) -> &[(i64, i64)] {
+        &self.filled_spans
+    }
+
+    /// Device events (only complete once the iterator is exhausted)
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Number of records or sectors that failed their checksum (only
+    /// complete once the iterator is exhausted)
+    pub fn checksum_mismatches(&self) -> usize {
+        self.checksum_mismatches
+    }
+
+    /// Sample rate observed between block timestamps (only set once the
+    /// iterator is exhausted)
+    pub fn effective_sample_rate(&self) -> Option<f64> {
+        self.effective_sample_rate
+    }
+
+    /// Configuration segments (only complete once the iterator is exhausted)
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+impl Drop for ChunkIterator {
+    fn drop(&mut self) {
+        // The worker notices at its next chunk boundary, or when its next
+        // send fails once the receiver is gone.
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for ChunkIterator {
+    type Item = Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let message = self.receiver.recv();
+            if matches!(message, Ok(Message::Finished(_)) | Err(_))
+                && let Some(worker) = self.worker.take()
+            {
+                // The worker is done (or gone), reap the thread.
+                let _ = worker.join();
+            }
+            match message {
+                Ok(Message::Metadata(entry)) => self.metadata.push(entry),
+                Ok(Message::Chunk(chunk)) => return Some(Ok(chunk)),
+                Ok(Message::Finished(Ok(result))) => {
+                    self.finished = true;
+                    self.warnings = result.warnings;
+                    self.filled_spans = result.filled_spans;
+                    self.events = result.events;
+                    self.checksum_mismatches = result.checksum_mismatches;
+                    self.effective_sample_rate = result.effective_sample_rate;
+                    self.segments = result.segments;
+                }
+                Ok(Message::Finished(Err(e))) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+                Err(_) => {
+                    self.finished = true;
+                    return Some(Err(ActfastError::Parse {
+                        format: self.format,
+                        message: "reader thread terminated unexpectedly".to_string(),
+                        location: crate::error::FileLocation::new(),
+                    }));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn test_file(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_data/cmi")
+            .join(name)
+    }
+
+    /// Chunks must concatenate to exactly the whole-file result.
+    fn check_chunks_match_whole_file(name: &str, chunk_samples: usize, expected_chunks: usize) {
+        let path = test_file(name);
+        let (format, file) = crate::open_and_identify(&path).unwrap();
+        let mut whole = HashMap::new();
+        crate::read_format(
+            format,
+            file,
+            ReadOptions::default(),
+            |_| {},
+            |tables| {
+                for table in tables {
+                    whole.insert(table.name, table);
+                }
+            },
+            false,
+        )
+        .unwrap();
+
+        let options = ReadOptions {
+            chunk_samples: Some(chunk_samples),
+            ..ReadOptions::default()
+        };
+        let mut iterator = ChunkIterator::open(&path, options, false).unwrap();
+        let mut datetimes: HashMap<&str, Vec<i64>> = HashMap::new();
+        let mut lengths: HashMap<(&str, usize), usize> = HashMap::new();
+        let mut num_chunks = 0;
+        for chunk in iterator.by_ref() {
+            let chunk = chunk.unwrap();
+            assert_eq!(chunk.tables.len(), whole.len());
+            for table in chunk.tables {
+                datetimes
+                    .entry(table.name)
+                    .or_default()
+                    .extend(&table.datetime);
+                for (i, data) in table.data.iter().enumerate() {
+                    *lengths.entry((table.name, i)).or_default() += data.data.len();
+                }
+            }
+            num_chunks += 1;
+        }
+
+        assert_eq!(num_chunks, expected_chunks);
+        assert!(!iterator.metadata().is_empty());
+        assert!(iterator.warnings().is_empty());
+        for (name, table) in whole.iter() {
+            assert_eq!(&datetimes[name], &table.datetime);
+            for (i, data) in table.data.iter().enumerate() {
+                assert_eq!(lengths[&(*name, i)], data.data.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunks_axivity() {
+        // 30 sectors × 120 samples, flushed every 5 sectors
+        check_chunks_match_whole_file("axivity_ax3.cwa", 600, 6);
+    }
+
+    #[test]
+    fn test_chunks_geneactiv() {
+        // 20 pages × 300 samples
+        check_chunks_match_whole_file("geneactiv.bin", 1000, 5);
+    }
+
+    #[test]
+    fn test_chunks_actigraph() {
+        // 81 seconds × 60 samples
+        check_chunks_match_whole_file("actigraph.gt3x", 600, 9);
+    }
+
+    #[test]
+    fn test_chunks_drop_stops_worker() {
+        let options = ReadOptions {
+            chunk_samples: Some(1),
+            ..ReadOptions::default()
+        };
+        let mut iterator =
+            ChunkIterator::open(&test_file("axivity_ax3.cwa"), options, false).unwrap();
+        assert!(iterator.next().unwrap().is_ok());
+        let worker = iterator.worker.take().unwrap();
+        let cancel = iterator.cancel.clone();
+        drop(iterator);
+
+        worker.join().unwrap();
+        assert!(cancel.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_chunks_unknown_format() {
+        let path = std::env::temp_dir().join("actfast_stream_unknown.bin");
+        std::fs::write(&path, b"UNKN0000").unwrap();
+        let result = ChunkIterator::open(&path, ReadOptions::default(), false);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ActfastError::UnknownFormat { .. })));
+    }
+}